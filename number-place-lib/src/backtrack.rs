@@ -0,0 +1,133 @@
+use rand::Rng;
+
+use crate::dlx::SudokuExactCover;
+use crate::grid::{Cell, Grid};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveResult {
+    Solved(Grid),
+    NoSolution,
+    MultipleSolutions,
+}
+
+// ランダムな完成盤面を作る。空の盤面から埋めるので必ず成功する
+pub fn generate_solution(rng: &mut impl Rng) -> Grid {
+    SudokuExactCover::with_random_order(&Grid::empty(), &[], rng)
+        .solutions(1)
+        .remove(0)
+}
+
+// 論理解法で詰まっても諦めず、バックトラックで解の有無と一意性まで判定する
+pub fn solve_exact(grid: &Grid) -> SolveResult {
+    let mut solutions = SudokuExactCover::new(grid, &[]).solutions(2);
+    match solutions.len() {
+        0 => SolveResult::NoSolution,
+        1 => SolveResult::Solved(solutions.remove(0)),
+        _ => SolveResult::MultipleSolutions,
+    }
+}
+
+// 解の個数を数える。limitに達した時点で探索を打ち切る
+pub fn count_solutions(grid: &Grid, limit: usize) -> usize {
+    SudokuExactCover::new(grid, &[]).count_solutions(limit)
+}
+
+pub fn has_unique_solution(grid: &Grid) -> bool {
+    count_solutions(grid, 2) == 1
+}
+
+// 一意解の問題について、取り除いても解が一意のままであるヒント
+pub fn redundant_clues(grid: &Grid) -> Vec<Cell> {
+    let mut cells: Vec<Cell> = Vec::new();
+    let mut reduced = *grid;
+    for (row_index, col_index, value) in grid.cells() {
+        if value.is_none() {
+            continue;
+        }
+        reduced.set(row_index, col_index, None);
+        if has_unique_solution(&reduced) {
+            cells.push(Cell::new(row_index, col_index));
+        }
+        reduced.set(row_index, col_index, value);
+    }
+    cells
+}
+
+// どのヒントを1つ取り除いても解が一意でなくなる
+pub fn is_minimal(grid: &Grid) -> bool {
+    has_unique_solution(grid) && redundant_clues(grid).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::SIZE;
+
+    const PROBLEM: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+    const SOLUTION: &str = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+    // ヒント17個の問題。17個のヒントで一意解なら、どのヒントも取り除けない
+    const MINIMAL: &str = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+    fn parse(text: &str) -> Grid {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn unique_puzzle() {
+        let problem = parse(PROBLEM);
+        assert_eq!(solve_exact(&problem), SolveResult::Solved(parse(SOLUTION)));
+        assert_eq!(count_solutions(&problem, 10), 1);
+        assert!(has_unique_solution(&problem));
+        assert!(!redundant_clues(&problem).is_empty());
+    }
+
+    #[test]
+    fn puzzle_with_several_solutions() {
+        let mut problem = parse(PROBLEM);
+        for col_index in 0..SIZE {
+            problem.set(0, col_index, None);
+            problem.set(8, col_index, None);
+        }
+        assert_eq!(count_solutions(&problem, 3), 3);
+        assert_eq!(solve_exact(&problem), SolveResult::MultipleSolutions);
+        assert!(!has_unique_solution(&problem));
+        assert!(!is_minimal(&problem));
+    }
+
+    #[test]
+    fn conflicting_givens() {
+        let mut problem = parse(PROBLEM);
+        problem.set(0, 2, problem.get(0, 0));
+        assert_eq!(solve_exact(&problem), SolveResult::NoSolution);
+        assert_eq!(count_solutions(&problem, 10), 0);
+        assert!(!has_unique_solution(&problem));
+    }
+
+    #[test]
+    fn empty_grid() {
+        assert_eq!(count_solutions(&Grid::empty(), 5), 5);
+        assert!(!has_unique_solution(&Grid::empty()));
+        assert!(!is_minimal(&Grid::empty()));
+    }
+
+    #[test]
+    fn minimal_puzzle() {
+        assert!(is_minimal(&parse(MINIMAL)));
+        assert!(redundant_clues(&parse(MINIMAL)).is_empty());
+        assert!(!is_minimal(&parse(PROBLEM)));
+        // 完成盤面はどのマスも取り除ける
+        assert_eq!(redundant_clues(&parse(SOLUTION)).len(), 81);
+        assert!(!is_minimal(&parse(SOLUTION)));
+    }
+
+    // 取り除けるヒントを1つずつ消していくと、最後は最小の問題になる
+    #[test]
+    fn removing_redundant_clues_gives_minimal_puzzle() {
+        let mut problem = parse(PROBLEM);
+        while let Some(cell) = redundant_clues(&problem).first() {
+            problem.set(cell.row_index, cell.col_index, None);
+        }
+        assert!(is_minimal(&problem));
+        assert_eq!(solve_exact(&problem), SolveResult::Solved(parse(SOLUTION)));
+    }
+}
@@ -0,0 +1,265 @@
+use crate::grid::{Cell, Digit, Grid, Unit, CELL_COUNT, SIZE};
+use crate::solver::{SolveStep, StepAction};
+
+const UNIT_COUNT: usize = SIZE * 3;
+
+pub fn digit_bit(digit: Digit) -> u16 {
+    1 << digit.get()
+}
+
+pub fn mask_digits(mask: u16) -> impl Iterator<Item = Digit> {
+    Digit::all().filter(move |x| mask & digit_bit(*x) != 0)
+}
+
+// 空きマスごとの候補をビットで持つ (bit n が数字n)。埋まったマスは0。
+// ユニットごとに、数字を候補に持つマスの位置 (Unit::positionのビット) も合わせて更新する。
+// givensは作成時に埋まっていたマス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateGrid {
+    givens: Grid,
+    grid: Grid,
+    masks: [u16; CELL_COUNT],
+    positions: [[u16; SIZE + 1]; UNIT_COUNT],
+    placed: [u16; UNIT_COUNT],
+}
+
+impl CandidateGrid {
+    pub fn new(grid: &Grid) -> CandidateGrid {
+        let mut candidates = CandidateGrid {
+            givens: *grid,
+            grid: *grid,
+            masks: [0; CELL_COUNT],
+            positions: [[0; SIZE + 1]; UNIT_COUNT],
+            placed: [0; UNIT_COUNT],
+        };
+
+        for (row_index, col_index, value) in grid.cells() {
+            let cell = Cell::new(row_index, col_index);
+            match value {
+                Some(digit) => {
+                    for unit in cell.units().iter() {
+                        candidates.placed[unit.index()] |= digit_bit(digit);
+                    }
+                }
+                None => {
+                    let used = grid.used_digits(row_index, col_index);
+                    for digit in Digit::all().filter(|x| !used.contains(x)) {
+                        candidates.add(cell, digit);
+                    }
+                }
+            }
+        }
+
+        candidates
+    }
+
+    fn add(&mut self, cell: Cell, digit: Digit) {
+        self.masks[cell.index()] |= digit_bit(digit);
+        for unit in cell.units().iter() {
+            self.positions[unit.index()][digit.get() as usize] |= 1 << unit.position(cell);
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn mask(&self, cell: Cell) -> u16 {
+        self.masks[cell.index()]
+    }
+
+    pub fn has(&self, cell: Cell, digit: Digit) -> bool {
+        self.mask(cell) & digit_bit(digit) != 0
+    }
+
+    pub fn count(&self, cell: Cell) -> u32 {
+        self.mask(cell).count_ones()
+    }
+
+    pub fn digits(&self, cell: Cell) -> impl Iterator<Item = Digit> {
+        mask_digits(self.mask(cell))
+    }
+
+    pub fn is_empty_cell(&self, cell: Cell) -> bool {
+        self.grid.get(cell.row_index, cell.col_index).is_none()
+    }
+
+    pub fn is_given(&self, cell: Cell) -> bool {
+        self.givens.get(cell.row_index, cell.col_index).is_some()
+    }
+
+    // ユニット内で数字digitを候補に持つマスの位置 (bit n がUnit::cell_at(n))
+    pub fn positions(&self, unit: Unit, digit: Digit) -> u16 {
+        self.positions[unit.index()][digit.get() as usize]
+    }
+
+    // ユニット内で数字digitを候補に持つマス
+    pub fn cells_with(&self, unit: Unit, digit: Digit) -> Vec<Cell> {
+        let positions = self.positions(unit, digit);
+        (0..SIZE)
+            .filter(|x| positions & (1 << x) != 0)
+            .map(|x| unit.cell_at(x))
+            .collect()
+    }
+
+    pub fn is_placed_in(&self, unit: Unit, digit: Digit) -> bool {
+        self.placed[unit.index()] & digit_bit(digit) != 0
+    }
+
+    // 数字digitの強リンク: ユニット内でdigitの入るマスがちょうど2つ
+    pub fn strong_links(&self, digit: Digit) -> Vec<(Unit, Cell, Cell)> {
+        Unit::all()
+            .filter(|x| self.positions(*x, digit).count_ones() == 2)
+            .map(|x| {
+                let cells = self.cells_with(x, digit);
+                (x, cells[0], cells[1])
+            })
+            .collect()
+    }
+
+    // 候補の無くなった空きマスや、数字を入れる場所の無くなったユニットがあれば矛盾
+    pub fn has_contradiction(&self) -> bool {
+        Cell::all().any(|x| self.is_empty_cell(x) && self.mask(x) == 0)
+            || Unit::all().any(|unit| {
+                Digit::all().any(|x| !self.is_placed_in(unit, x) && self.positions(unit, x) == 0)
+            })
+    }
+
+    // 数字を入れ、同じ行・列・ブロックのマスからその数字を除外する
+    pub fn place(&mut self, cell: Cell, digit: Digit) {
+        for other in mask_digits(self.mask(cell)) {
+            self.eliminate(cell, other);
+        }
+        self.grid.set(cell.row_index, cell.col_index, Some(digit));
+        for unit in cell.units().iter() {
+            self.placed[unit.index()] |= digit_bit(digit);
+            for other in unit.cells() {
+                self.eliminate(other, digit);
+            }
+        }
+    }
+
+    // 候補を除外する。候補に無かった場合はfalse
+    pub fn eliminate(&mut self, cell: Cell, digit: Digit) -> bool {
+        if !self.has(cell, digit) {
+            return false;
+        }
+
+        self.masks[cell.index()] &= !digit_bit(digit);
+        for unit in cell.units().iter() {
+            self.positions[unit.index()][digit.get() as usize] &= !(1 << unit.position(cell));
+        }
+        true
+    }
+
+    pub fn apply(&mut self, step: &SolveStep) {
+        match &step.action {
+            StepAction::Place(cell, digit) => self.place(*cell, *digit),
+            StepAction::Eliminate(candidates) => {
+                for candidate in candidates {
+                    self.eliminate(candidate.cell, candidate.digit);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBLEM: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    fn digit(value: u8) -> Digit {
+        Digit::new(value).unwrap()
+    }
+
+    // ユニットごとの位置のビットが、マスごとの候補と一致している
+    fn assert_positions_in_sync(candidates: &CandidateGrid) {
+        for unit in Unit::all() {
+            for x in Digit::all() {
+                let cells: Vec<Cell> = unit.cells().filter(|cell| candidates.has(*cell, x)).collect();
+                assert_eq!(candidates.cells_with(unit, x), cells, "{} in {}", x, unit);
+                assert_eq!(candidates.positions(unit, x).count_ones() as usize, cells.len());
+            }
+        }
+    }
+
+    #[test]
+    fn new_candidates_exclude_used_digits() {
+        let grid: Grid = PROBLEM.parse().unwrap();
+        let candidates = CandidateGrid::new(&grid);
+        for (row_index, col_index, value) in grid.cells() {
+            let cell = Cell::new(row_index, col_index);
+            match value {
+                Some(x) => {
+                    assert!(candidates.is_given(cell));
+                    assert_eq!(candidates.mask(cell), 0);
+                    assert!(cell.units().iter().all(|unit| candidates.is_placed_in(*unit, x)));
+                }
+                None => {
+                    let used = grid.used_digits(row_index, col_index);
+                    let digits: Vec<Digit> = Digit::all().filter(|x| !used.contains(x)).collect();
+                    assert_eq!(candidates.digits(cell).collect::<Vec<Digit>>(), digits);
+                }
+            }
+        }
+        assert_positions_in_sync(&candidates);
+        assert_eq!(CandidateGrid::new(&Grid::empty()).count(Cell::new(4, 4)), 9);
+    }
+
+    #[test]
+    fn place_and_eliminate_keep_positions_in_sync() {
+        let mut candidates = CandidateGrid::new(&PROBLEM.parse().unwrap());
+        let cell = Cell::new(0, 2);
+        assert!(candidates.has(cell, digit(4)));
+
+        candidates.place(cell, digit(4));
+        assert_eq!(candidates.grid().get(0, 2), Some(digit(4)));
+        assert!(!candidates.is_given(cell));
+        assert!(!candidates.is_empty_cell(cell));
+        assert_eq!(candidates.mask(cell), 0);
+        assert!(cell.units().iter().all(|unit| candidates.is_placed_in(*unit, digit(4))));
+        assert!(cell.units().iter().all(|unit| candidates.positions(*unit, digit(4)) == 0));
+        assert_positions_in_sync(&candidates);
+
+        let other = Cell::new(2, 0);
+        assert!(candidates.eliminate(other, digit(2)));
+        assert!(!candidates.eliminate(other, digit(2)));
+        assert!(!candidates.has(other, digit(2)));
+        assert_positions_in_sync(&candidates);
+    }
+
+    #[test]
+    fn strong_links() {
+        let mut candidates = CandidateGrid::new(&Grid::empty());
+        assert!(candidates.strong_links(digit(1)).is_empty());
+
+        for col_index in [1, 2, 3, 5, 6, 7, 8] {
+            candidates.eliminate(Cell::new(0, col_index), digit(1));
+        }
+        assert_eq!(candidates.strong_links(digit(1)), vec![(Unit::Row(0), Cell::new(0, 0), Cell::new(0, 4))]);
+        assert!(candidates.strong_links(digit(2)).is_empty());
+    }
+
+    #[test]
+    fn contradictions() {
+        let candidates = CandidateGrid::new(&PROBLEM.parse().unwrap());
+        assert!(!candidates.has_contradiction());
+
+        // 候補の無くなった空きマス
+        let mut no_digit = candidates.clone();
+        let cell = Cell::new(0, 2);
+        for x in candidates.digits(cell) {
+            no_digit.eliminate(cell, x);
+        }
+        assert!(no_digit.has_contradiction());
+
+        // 数字を入れる場所の無くなったユニット
+        let mut no_place = candidates.clone();
+        for cell in Unit::Row(0).cells() {
+            no_place.eliminate(cell, digit(1));
+        }
+        assert!(no_place.has_contradiction());
+    }
+}
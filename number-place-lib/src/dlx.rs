@@ -0,0 +1,418 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::grid::{Cell, Digit, Grid, SIZE};
+
+const ROOT: usize = 0;
+
+// Dancing Linksによる完全被覆問題 (Algorithm X)。
+// 主列はちょうど1回、副列は高々1回だけ被覆される行の組み合わせを探す
+pub struct ExactCover {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    row: Vec<usize>,
+    size: Vec<usize>,
+    column_count: usize,
+    row_count: usize,
+}
+
+impl ExactCover {
+    // 列ヘッダはノード1からで、主列のあとに副列が続く
+    pub fn new(primary_columns: usize, secondary_columns: usize) -> ExactCover {
+        let column_count = primary_columns + secondary_columns;
+        let mut cover = ExactCover {
+            left: Vec::new(),
+            right: Vec::new(),
+            up: Vec::new(),
+            down: Vec::new(),
+            column: Vec::new(),
+            row: Vec::new(),
+            size: vec![0; column_count + 1],
+            column_count,
+            row_count: 0,
+        };
+
+        for node in 0..=column_count {
+            let is_primary = node <= primary_columns;
+            cover.left.push(match (node, is_primary) {
+                (ROOT, _) => primary_columns,
+                (_, true) => node - 1,
+                (_, false) => node,
+            });
+            cover.right.push(match is_primary {
+                true if node == primary_columns => ROOT,
+                true => node + 1,
+                false => node,
+            });
+            cover.up.push(node);
+            cover.down.push(node);
+            cover.column.push(node);
+            cover.row.push(usize::MAX);
+        }
+
+        cover
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.column_count
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    // 行を追加して行番号を返す。列番号は0始まり
+    pub fn add_row(&mut self, columns: &[usize]) -> usize {
+        let row_id = self.row_count;
+        self.row_count += 1;
+
+        let mut first: Option<usize> = None;
+        for column_index in columns {
+            let header = column_index + 1;
+            let node = self.left.len();
+
+            self.column.push(header);
+            self.row.push(row_id);
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+
+            match first {
+                None => {
+                    self.left.push(node);
+                    self.right.push(node);
+                    first = Some(node);
+                }
+                Some(first) => {
+                    let last = self.left[first];
+                    self.left.push(last);
+                    self.right.push(first);
+                    self.right[last] = node;
+                    self.left[first] = node;
+                }
+            }
+        }
+
+        row_id
+    }
+
+    fn cover(&mut self, header: usize) {
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = right;
+        self.left[right] = left;
+
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (up, down) = (self.up[j], self.down[j]);
+                self.down[up] = down;
+                self.up[down] = up;
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                let (up, down) = (self.up[j], self.down[j]);
+                self.down[up] = j;
+                self.up[down] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = header;
+        self.left[right] = header;
+    }
+
+    // 候補の最も少ない主列を選ぶ
+    fn choose_column(&self) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        let mut header = self.right[ROOT];
+        while header != ROOT {
+            if best.is_none_or(|x| self.size[header] < self.size[x]) {
+                best = Some(header);
+                if self.size[header] <= 1 {
+                    break;
+                }
+            }
+            header = self.right[header];
+        }
+        best
+    }
+
+    // 見つかった解ごとにon_solutionを呼び、limit個で打ち切る。探索後は元の状態に戻る
+    fn search(&mut self, limit: usize, rows: &mut Vec<usize>, found: &mut usize, on_solution: &mut dyn FnMut(&[usize])) {
+        let header = match self.choose_column() {
+            Some(header) => header,
+            None => {
+                *found += 1;
+                on_solution(rows);
+                return;
+            }
+        };
+        if self.size[header] == 0 {
+            return;
+        }
+
+        self.cover(header);
+        let mut i = self.down[header];
+        while i != header && *found < limit {
+            rows.push(self.row[i]);
+            let mut j = self.right[i];
+            while j != i {
+                self.cover(self.column[j]);
+                j = self.right[j];
+            }
+
+            self.search(limit, rows, found, on_solution);
+
+            let mut j = self.left[i];
+            while j != i {
+                self.uncover(self.column[j]);
+                j = self.left[j];
+            }
+            rows.pop();
+            i = self.down[i];
+        }
+        self.uncover(header);
+    }
+
+    // 解を行番号の組としてlimit個まで返す
+    pub fn solve(&mut self, limit: usize) -> Vec<Vec<usize>> {
+        let mut solutions: Vec<Vec<usize>> = Vec::new();
+        let mut found = 0;
+        if limit > 0 {
+            self.search(limit, &mut Vec::new(), &mut found, &mut |rows| solutions.push(rows.to_vec()));
+        }
+        solutions
+    }
+
+    pub fn count_solutions(&mut self, limit: usize) -> usize {
+        let mut found = 0;
+        if limit > 0 {
+            self.search(limit, &mut Vec::new(), &mut found, &mut |_| {});
+        }
+        found
+    }
+}
+
+// 数独の制約行列。列はマス、行と数字、列と数字、ブロックと数字、追加の領域と数字の順。
+// 9マスの追加領域は各数字がちょうど1回、それより小さい領域は高々1回入る
+pub struct SudokuExactCover {
+    cover: ExactCover,
+    candidates: Vec<(Cell, Digit)>,
+}
+
+impl SudokuExactCover {
+    pub fn new(grid: &Grid, extra_regions: &[Vec<Cell>]) -> SudokuExactCover {
+        SudokuExactCover::build(extra_regions, candidate_list(grid))
+    }
+
+    // 行の並びをランダムにして、見つかる解の順番を変える
+    pub fn with_random_order(grid: &Grid, extra_regions: &[Vec<Cell>], rng: &mut impl Rng) -> SudokuExactCover {
+        let mut candidates = candidate_list(grid);
+        candidates.shuffle(rng);
+        SudokuExactCover::build(extra_regions, candidates)
+    }
+
+    // candidatesの並びがそのまま行番号になる
+    fn build(extra_regions: &[Vec<Cell>], candidates: Vec<(Cell, Digit)>) -> SudokuExactCover {
+        let (full_regions, partial_regions): (Vec<&Vec<Cell>>, Vec<&Vec<Cell>>) = extra_regions.iter()
+            .partition(|x| x.len() == SIZE);
+        let primary_columns = SIZE * SIZE * 4 + full_regions.len() * SIZE;
+        let mut cover = ExactCover::new(primary_columns, partial_regions.len() * SIZE);
+
+        for (cell, digit) in candidates.iter() {
+            let digit_index = digit.get() as usize - 1;
+            let mut columns = vec![
+                cell.index(),
+                SIZE * SIZE + cell.row_index * SIZE + digit_index,
+                SIZE * SIZE * 2 + cell.col_index * SIZE + digit_index,
+                SIZE * SIZE * 3 + cell.block_index() * SIZE + digit_index,
+            ];
+            let regions = full_regions.iter()
+                .chain(partial_regions.iter())
+                .enumerate();
+            for (region_index, region) in regions {
+                if region.contains(cell) {
+                    columns.push(SIZE * SIZE * 4 + region_index * SIZE + digit_index);
+                }
+            }
+            cover.add_row(&columns);
+        }
+
+        SudokuExactCover { cover, candidates }
+    }
+
+    pub fn solutions(&mut self, limit: usize) -> Vec<Grid> {
+        let candidates = &self.candidates;
+        self.cover.solve(limit).iter()
+            .map(|rows| {
+                let mut grid = Grid::empty();
+                for row_id in rows {
+                    let (cell, digit) = candidates[*row_id];
+                    grid.set(cell.row_index, cell.col_index, Some(digit));
+                }
+                grid
+            })
+            .collect()
+    }
+
+    pub fn count_solutions(&mut self, limit: usize) -> usize {
+        self.cover.count_solutions(limit)
+    }
+}
+
+// 埋まっているマスはその数字だけを候補にする
+fn candidate_list(grid: &Grid) -> Vec<(Cell, Digit)> {
+    Cell::all()
+        .flat_map(|cell| Digit::all().map(move |digit| (cell, digit)))
+        .filter(|(cell, digit)| grid.get(cell.row_index, cell.col_index).is_none_or(|x| x == *digit))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBLEM: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+    const SOLUTION: &str = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn parse(text: &str) -> Grid {
+        text.parse().unwrap()
+    }
+
+    fn diagonals() -> Vec<Vec<Cell>> {
+        vec![
+            (0..SIZE).map(|x| Cell::new(x, x)).collect(),
+            (0..SIZE).map(|x| Cell::new(x, SIZE - 1 - x)).collect(),
+        ]
+    }
+
+    fn sorted(mut solutions: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+        for rows in solutions.iter_mut() {
+            rows.sort_unstable();
+        }
+        solutions.sort();
+        solutions
+    }
+
+    // Knuthの論文の例。解は行0, 3, 4の1つだけ
+    #[test]
+    fn exact_cover_with_primary_columns() {
+        let mut cover = ExactCover::new(7, 0);
+        for columns in [vec![2, 4, 5], vec![0, 3, 6], vec![1, 2, 5], vec![0, 3], vec![1, 6], vec![3, 4, 6]] {
+            cover.add_row(&columns);
+        }
+        assert_eq!(cover.column_count(), 7);
+        assert_eq!(cover.row_count(), 6);
+
+        assert_eq!(sorted(cover.solve(10)), vec![vec![0, 3, 4]]);
+        // 探索後は元に戻っているので、もう一度解いても同じ
+        assert_eq!(cover.count_solutions(10), 1);
+        assert!(cover.solve(0).is_empty());
+    }
+
+    // 副列は被覆されなくてもよいが、2回被覆されてはいけない
+    #[test]
+    fn exact_cover_with_secondary_columns() {
+        let mut cover = ExactCover::new(2, 1);
+        for columns in [vec![0, 2], vec![1, 2], vec![0], vec![1]] {
+            cover.add_row(&columns);
+        }
+
+        assert_eq!(sorted(cover.solve(10)), vec![vec![0, 3], vec![1, 2], vec![2, 3]]);
+        assert_eq!(cover.count_solutions(2), 2);
+    }
+
+    #[test]
+    fn unique_puzzle() {
+        let (problem, solution) = (parse(PROBLEM), parse(SOLUTION));
+        assert_eq!(SudokuExactCover::new(&problem, &[]).solutions(10), vec![solution]);
+        assert_eq!(SudokuExactCover::new(&problem, &[]).count_solutions(10), 1);
+    }
+
+    #[test]
+    fn puzzle_with_several_solutions() {
+        let mut problem = parse(PROBLEM);
+        for col_index in 0..SIZE {
+            problem.set(0, col_index, None);
+            problem.set(8, col_index, None);
+        }
+        let solutions = SudokuExactCover::new(&problem, &[]).solutions(3);
+        assert_eq!(solutions.len(), 3);
+        for solution in solutions {
+            assert!(solution.is_solved());
+            for (row_index, col_index, value) in problem.cells().filter(|x| x.2.is_some()) {
+                assert_eq!(solution.get(row_index, col_index), value);
+            }
+        }
+        assert_eq!(SudokuExactCover::new(&problem, &[]).count_solutions(3), 3);
+    }
+
+    #[test]
+    fn conflicting_givens() {
+        let mut problem = parse(PROBLEM);
+        problem.set(0, 2, problem.get(0, 0));
+        assert!(SudokuExactCover::new(&problem, &[]).solutions(1).is_empty());
+        assert_eq!(SudokuExactCover::new(&problem, &[]).count_solutions(10), 0);
+    }
+
+    #[test]
+    fn empty_grid() {
+        let solutions = SudokuExactCover::new(&Grid::empty(), &[]).solutions(5);
+        assert_eq!(solutions.len(), 5);
+        assert!(solutions.iter().all(|x| x.is_solved()));
+        assert_eq!(SudokuExactCover::new(&Grid::empty(), &[]).count_solutions(5), 5);
+    }
+
+    // 対角線を追加の領域にする (X-sudoku)。9マスの領域なので各数字がちょうど1回入る
+    #[test]
+    fn diagonal_regions() {
+        let regions = diagonals();
+        let solutions = SudokuExactCover::new(&Grid::empty(), &regions).solutions(3);
+        assert_eq!(solutions.len(), 3);
+        for solution in solutions {
+            assert!(solution.is_solved());
+            for region in regions.iter() {
+                let mut digits: Vec<Digit> = region.iter()
+                    .map(|x| solution.get(x.row_index, x.col_index).unwrap())
+                    .collect();
+                digits.sort();
+                digits.dedup();
+                assert_eq!(digits.len(), SIZE);
+            }
+        }
+
+        // 通常の解はr2c2とr4c4がどちらも7で対角線の制約を満たさないので、同じヒントでは解が無い
+        let solution = parse(SOLUTION);
+        assert_eq!(solution.get(1, 1), solution.get(3, 3));
+        assert_eq!(SudokuExactCover::new(&parse(PROBLEM), &regions).count_solutions(2), 0);
+    }
+
+    // 9マス未満の領域では同じ数字が高々1回入る。r9c9の解は9なので、ヒントの9と同じ領域にすると解が無い
+    #[test]
+    fn partial_region() {
+        let mut problem = parse(PROBLEM);
+        problem.set(8, 8, None);
+        let solutions = SudokuExactCover::new(&problem, &[vec![Cell::new(0, 0), Cell::new(8, 8)]]).solutions(10);
+        assert_eq!(solutions, vec![parse(SOLUTION)]);
+        assert_eq!(SudokuExactCover::new(&problem, &[vec![Cell::new(2, 1), Cell::new(8, 8)]]).count_solutions(10), 0);
+    }
+}
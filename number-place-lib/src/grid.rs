@@ -0,0 +1,465 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+pub const SIZE: usize = 9;
+pub const CELL_COUNT: usize = SIZE * SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Digit(u8);
+
+impl Digit {
+    pub fn new(value: u8) -> Option<Digit> {
+        match value {
+            1..=9 => Some(Digit(value)),
+            _ => None,
+        }
+    }
+
+    pub fn from_i32(value: i32) -> Option<Digit> {
+        match value {
+            1..=9 => Some(Digit(value as u8)),
+            _ => None,
+        }
+    }
+
+    pub fn get(self) -> u8 {
+        self.0
+    }
+
+    pub fn all() -> impl Iterator<Item = Digit> {
+        (1..=9).map(Digit)
+    }
+}
+
+impl fmt::Display for Digit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    InvalidRowCount(usize),
+    InvalidColCount { row_index: usize, len: usize },
+    InvalidValue { row_index: usize, col_index: usize, value: i32 },
+    InvalidCellCount(usize),
+    InvalidChar(char),
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::InvalidRowCount(len) => write!(f, "expected 9 rows, found {}", len),
+            GridError::InvalidColCount { row_index, len } => write!(f, "expected 9 columns in row {}, found {}", row_index, len),
+            GridError::InvalidValue { row_index, col_index, value } => write!(f, "invalid value {} at {}-{}", value, row_index, col_index),
+            GridError::InvalidCellCount(len) => write!(f, "expected 81 cells, found {}", len),
+            GridError::InvalidChar(c) => write!(f, "invalid character '{}'", c),
+        }
+    }
+}
+
+impl Error for GridError {}
+
+pub fn block_index(row_index: usize, col_index: usize) -> usize {
+    (row_index / 3) * 3 + col_index / 3
+}
+
+pub fn block_origin(block_index: usize) -> (usize, usize) {
+    ((block_index / 3) * 3, (block_index % 3) * 3)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cell {
+    pub row_index: usize,
+    pub col_index: usize,
+}
+
+impl Cell {
+    pub fn new(row_index: usize, col_index: usize) -> Cell {
+        Cell { row_index, col_index }
+    }
+
+    pub fn from_index(index: usize) -> Cell {
+        Cell::new(index / SIZE, index % SIZE)
+    }
+
+    pub fn index(self) -> usize {
+        self.row_index * SIZE + self.col_index
+    }
+
+    pub fn block_index(self) -> usize {
+        block_index(self.row_index, self.col_index)
+    }
+
+    pub fn all() -> impl Iterator<Item = Cell> {
+        (0..CELL_COUNT).map(Cell::from_index)
+    }
+
+    // 同じ行・列・ブロックにある別のマスか
+    pub fn sees(self, other: Cell) -> bool {
+        self != other
+            && (self.row_index == other.row_index
+                || self.col_index == other.col_index
+                || self.block_index() == other.block_index())
+    }
+
+    // マスの属する行・列・ブロック
+    pub fn units(self) -> [Unit; 3] {
+        [
+            Unit::Row(self.row_index),
+            Unit::Col(self.col_index),
+            Unit::Block(self.block_index()),
+        ]
+    }
+}
+
+// 行・列は1始まりの r1c1 形式で表示する
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "r{}c{}", self.row_index + 1, self.col_index + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Row(usize),
+    Col(usize),
+    Block(usize),
+}
+
+impl Unit {
+    // ブロック、行、列の順
+    pub fn all() -> impl Iterator<Item = Unit> {
+        (0..SIZE).map(Unit::Block)
+            .chain((0..SIZE).map(Unit::Row))
+            .chain((0..SIZE).map(Unit::Col))
+    }
+
+    pub fn cells(self) -> impl Iterator<Item = Cell> {
+        (0..SIZE).map(move |i| self.cell_at(i))
+    }
+
+    pub fn contains(self, cell: Cell) -> bool {
+        match self {
+            Unit::Row(row_index) => cell.row_index == row_index,
+            Unit::Col(col_index) => cell.col_index == col_index,
+            Unit::Block(block_index) => cell.block_index() == block_index,
+        }
+    }
+
+    // all()での並び順の番号 (0-26)
+    pub fn index(self) -> usize {
+        match self {
+            Unit::Block(block_index) => block_index,
+            Unit::Row(row_index) => SIZE + row_index,
+            Unit::Col(col_index) => SIZE * 2 + col_index,
+        }
+    }
+
+    // ユニット内でのマスの位置 (0-8)。cells()の並び順と同じ
+    pub fn position(self, cell: Cell) -> usize {
+        match self {
+            Unit::Row(_) => cell.col_index,
+            Unit::Col(_) => cell.row_index,
+            Unit::Block(_) => cell.row_index % 3 * 3 + cell.col_index % 3,
+        }
+    }
+
+    pub fn cell_at(self, position: usize) -> Cell {
+        match self {
+            Unit::Row(row_index) => Cell::new(row_index, position),
+            Unit::Col(col_index) => Cell::new(position, col_index),
+            Unit::Block(block_index) => {
+                let (top, left) = block_origin(block_index);
+                Cell::new(top + position / 3, left + position % 3)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unit::Row(index) => write!(f, "row {}", index + 1),
+            Unit::Col(index) => write!(f, "col {}", index + 1),
+            Unit::Block(index) => write!(f, "block {}", index + 1),
+        }
+    }
+}
+
+// 9x9固定のマス目。空きマスはNone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Grid {
+    cells: [Option<Digit>; CELL_COUNT],
+}
+
+impl Grid {
+    pub fn empty() -> Grid {
+        Grid {
+            cells: [None; CELL_COUNT],
+        }
+    }
+
+    // 0を空きマスとして扱う
+    pub fn from_lines(lines: &[Vec<i32>]) -> Result<Grid, GridError> {
+        if lines.len() != SIZE {
+            return Err(GridError::InvalidRowCount(lines.len()));
+        }
+
+        let mut grid = Grid::empty();
+        for (row_index, row) in lines.iter().enumerate() {
+            if row.len() != SIZE {
+                return Err(GridError::InvalidColCount { row_index, len: row.len() });
+            }
+            for (col_index, value) in row.iter().enumerate() {
+                let digit = match value {
+                    0 => None,
+                    _ => Some(Digit::from_i32(*value).ok_or(GridError::InvalidValue {
+                        row_index,
+                        col_index,
+                        value: *value,
+                    })?),
+                };
+                grid.set(row_index, col_index, digit);
+            }
+        }
+
+        Ok(grid)
+    }
+
+    pub fn to_lines(&self) -> Vec<Vec<i32>> {
+        (0..SIZE)
+            .map(|row_index| self.row(row_index).map(digit_to_i32).collect())
+            .collect()
+    }
+
+    pub fn get(&self, row_index: usize, col_index: usize) -> Option<Digit> {
+        self.cells[row_index * SIZE + col_index]
+    }
+
+    pub fn set(&mut self, row_index: usize, col_index: usize, value: Option<Digit>) {
+        self.cells[row_index * SIZE + col_index] = value;
+    }
+
+    pub fn row(&self, row_index: usize) -> impl Iterator<Item = Option<Digit>> + '_ {
+        (0..SIZE).map(move |col_index| self.get(row_index, col_index))
+    }
+
+    pub fn col(&self, col_index: usize) -> impl Iterator<Item = Option<Digit>> + '_ {
+        (0..SIZE).map(move |row_index| self.get(row_index, col_index))
+    }
+
+    pub fn block(&self, block_index: usize) -> impl Iterator<Item = Option<Digit>> + '_ {
+        let (top, left) = block_origin(block_index);
+        (0..SIZE).map(move |i| self.get(top + i / 3, left + i % 3))
+    }
+
+    // (行, 列, 値) を左上から順に返す
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, Option<Digit>)> + '_ {
+        self.cells.iter()
+            .enumerate()
+            .map(|(index, value)| (index / SIZE, index % SIZE, *value))
+    }
+
+    pub fn count_empty(&self) -> usize {
+        self.cells.iter().filter(|x| x.is_none()).count()
+    }
+
+    // 指定マスの行・列・ブロックで使用済みの数字
+    pub fn used_digits(&self, row_index: usize, col_index: usize) -> Vec<Digit> {
+        let mut used: Vec<Digit> = self.row(row_index)
+            .chain(self.col(col_index))
+            .chain(self.block(block_index(row_index, col_index)))
+            .flatten()
+            .collect();
+        used.sort();
+        used.dedup();
+        used
+    }
+
+    // 同じ行・列・ブロックに同じ数字が2つ以上ある
+    pub fn has_conflict(&self) -> bool {
+        (0..SIZE).any(|i| {
+            has_duplicate(self.row(i))
+                || has_duplicate(self.col(i))
+                || has_duplicate(self.block(i))
+        })
+    }
+
+    pub fn is_solved(&self) -> bool {
+        if self.count_empty() != 0 {
+            return false;
+        }
+
+        (0..SIZE).all(|i| {
+            is_complete_unit(self.row(i))
+                && is_complete_unit(self.col(i))
+                && is_complete_unit(self.block(i))
+        })
+    }
+}
+
+impl Default for Grid {
+    fn default() -> Grid {
+        Grid::empty()
+    }
+}
+
+fn digit_to_i32(value: Option<Digit>) -> i32 {
+    value.map(|x| x.get() as i32).unwrap_or(0)
+}
+
+fn is_complete_unit(values: impl Iterator<Item = Option<Digit>>) -> bool {
+    let mut seen = [false; SIZE + 1];
+    for value in values {
+        match value {
+            Some(digit) if !seen[digit.get() as usize] => seen[digit.get() as usize] = true,
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn has_duplicate(values: impl Iterator<Item = Option<Digit>>) -> bool {
+    let mut seen = [false; SIZE + 1];
+    for digit in values.flatten() {
+        if seen[digit.get() as usize] {
+            return true;
+        }
+        seen[digit.get() as usize] = true;
+    }
+    false
+}
+
+// 81文字の数字列を読み込む。'0'と'.'は空きマス、空白と改行は無視する
+impl FromStr for Grid {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Grid, GridError> {
+        let mut values: Vec<Option<Digit>> = Vec::new();
+        for c in s.chars().filter(|c| !c.is_whitespace()) {
+            let value = match c {
+                '0' | '.' => None,
+                '1'..='9' => Digit::new(c as u8 - b'0'),
+                _ => return Err(GridError::InvalidChar(c)),
+            };
+            values.push(value);
+        }
+
+        if values.len() != CELL_COUNT {
+            return Err(GridError::InvalidCellCount(values.len()));
+        }
+
+        let mut grid = Grid::empty();
+        grid.cells.copy_from_slice(&values);
+        Ok(grid)
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row_index in 0..SIZE {
+            if row_index > 0 {
+                writeln!(f)?;
+            }
+            for value in self.row(row_index) {
+                match value {
+                    Some(digit) => write!(f, "{}", digit)?,
+                    None => write!(f, ".")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBLEM: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+    const SOLUTION: &str = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn parse(s: &str) -> Grid {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn from_lines() {
+        let grid = parse(PROBLEM);
+        let lines = grid.to_lines();
+        assert_eq!(Grid::from_lines(&lines), Ok(grid));
+        assert_eq!(lines[0], vec![5, 3, 0, 0, 7, 0, 0, 0, 0]);
+
+        assert_eq!(Grid::from_lines(&lines[..8]), Err(GridError::InvalidRowCount(8)));
+
+        let mut short = lines.clone();
+        short[2].pop();
+        assert_eq!(Grid::from_lines(&short), Err(GridError::InvalidColCount { row_index: 2, len: 8 }));
+
+        let mut out_of_range = lines.clone();
+        out_of_range[4][6] = 10;
+        assert_eq!(
+            Grid::from_lines(&out_of_range),
+            Err(GridError::InvalidValue { row_index: 4, col_index: 6, value: 10 }),
+        );
+        out_of_range[4][6] = -1;
+        assert_eq!(
+            Grid::from_lines(&out_of_range),
+            Err(GridError::InvalidValue { row_index: 4, col_index: 6, value: -1 }),
+        );
+    }
+
+    #[test]
+    fn from_str_and_display() {
+        let grid = parse(PROBLEM);
+        assert_eq!(grid.get(0, 0), Digit::new(5));
+        assert_eq!(grid.get(0, 2), None);
+        assert_eq!(grid.count_empty(), 51);
+
+        let text = grid.to_string();
+        assert_eq!(text.lines().next(), Some("53..7...."));
+        assert_eq!(text.lines().count(), 9);
+        assert_eq!(parse(&text), grid);
+        assert_eq!(parse(&PROBLEM.replace('0', ".")), grid);
+
+        assert_eq!(PROBLEM[1..].parse::<Grid>(), Err(GridError::InvalidCellCount(80)));
+        assert_eq!(PROBLEM.replace('7', "x").parse::<Grid>(), Err(GridError::InvalidChar('x')));
+    }
+
+    #[test]
+    fn conflicts_and_solved() {
+        assert!(!parse(PROBLEM).has_conflict());
+        assert!(!parse(PROBLEM).is_solved());
+        assert!(!parse(SOLUTION).has_conflict());
+        assert!(parse(SOLUTION).is_solved());
+        assert!(!Grid::empty().has_conflict());
+        assert!(!Grid::empty().is_solved());
+
+        // r1c3に行の5を重ねる
+        let mut in_row = parse(PROBLEM);
+        in_row.set(0, 2, Digit::new(5));
+        assert!(in_row.has_conflict());
+
+        // 2つのマスを入れ替えると、重複は無いが列とブロックが揃わない
+        let mut swapped = parse(SOLUTION);
+        swapped.set(0, 0, Digit::new(3));
+        swapped.set(0, 1, Digit::new(5));
+        assert!(swapped.has_conflict());
+        assert!(!swapped.is_solved());
+    }
+
+    #[test]
+    fn unit_positions() {
+        for unit in Unit::all() {
+            for (position, cell) in unit.cells().enumerate() {
+                assert!(unit.contains(cell));
+                assert_eq!(unit.position(cell), position);
+                assert_eq!(unit.cell_at(position), cell);
+            }
+        }
+        assert_eq!(Unit::Block(4).cell_at(5), Cell::new(4, 5));
+        assert_eq!(Unit::Block(8).position(Cell::new(7, 6)), 3);
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::grid::{Grid, Unit};
+use crate::solver::{solve_with_options, SolveStep, SolverOptions, Technique};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+    Master,
+    Unsolvable,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+            Difficulty::Master => "master",
+            Difficulty::Unsolvable => "unsolvable",
+        }
+    }
+
+    // 最も難しい手法の点数から区分を決める。仮定を置く手法 (7.0超) が必要なものは最上位のmaster
+    pub fn from_score(score: f32) -> Difficulty {
+        if score <= 1.5 {
+            Difficulty::Easy
+        } else if score <= 2.8 {
+            Difficulty::Medium
+        } else if score <= 4.4 {
+            Difficulty::Hard
+        } else if score <= 7.0 {
+            Difficulty::Expert
+        } else {
+            Difficulty::Master
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            "expert" => Ok(Difficulty::Expert),
+            "master" => Ok(Difficulty::Master),
+            "unsolvable" => Ok(Difficulty::Unsolvable),
+            _ => Err(format!("unknown difficulty: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// scoreはSudoku Explainerに倣った点数で、必要な手法のうち最も難しいものの点数
+// stepsは解くのにかかった手順の数で、scoreと区分には影響しない
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+    pub score: f32,
+    pub hardest: Option<Technique>,
+    pub steps: usize,
+    pub difficulty: Difficulty,
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:.1} ({} steps)", self.difficulty, self.score, self.steps)
+    }
+}
+
+pub fn technique_score(technique: Technique) -> f32 {
+    match technique {
+        Technique::HiddenSingle => 1.5,
+        Technique::NakedSingle => 2.3,
+        Technique::Pointing => 2.6,
+        Technique::BoxLineReduction => 2.8,
+        Technique::NakedPair => 3.0,
+        Technique::XWing => 3.2,
+        Technique::Skyscraper => 3.3,
+        Technique::TwoStringKite => 3.3,
+        Technique::HiddenPair => 3.4,
+        Technique::EmptyRectangle => 3.4,
+        Technique::FinnedXWing => 3.4,
+        Technique::SashimiXWing => 3.5,
+        Technique::NakedTriple => 3.6,
+        Technique::Swordfish => 3.8,
+        Technique::HiddenTriple => 4.0,
+        Technique::FinnedSwordfish => 4.0,
+        Technique::SashimiSwordfish => 4.1,
+        Technique::XYWing => 4.2,
+        Technique::XYZWing => 4.4,
+        Technique::WWing => 4.4,
+        Technique::UniqueRectangleType1 => 4.5,
+        Technique::AvoidableRectangle => 4.5,
+        Technique::SimpleColoring => 4.5,
+        Technique::UniqueRectangleType2 => 4.6,
+        Technique::UniqueRectangleType3 => 4.6,
+        Technique::UniqueRectangleType4 => 4.6,
+        Technique::UniqueRectangleType5 => 4.6,
+        Technique::UniqueRectangleType6 => 4.6,
+        Technique::MultiColoring => 4.7,
+        Technique::XChain => 4.8,
+        Technique::NakedQuad => 5.0,
+        Technique::XYChain => 5.0,
+        Technique::SueDeCoq => 5.0,
+        Technique::Jellyfish => 5.2,
+        Technique::HiddenQuad => 5.4,
+        Technique::FinnedJellyfish => 5.4,
+        Technique::AlsXz => 5.5,
+        Technique::SashimiJellyfish => 5.5,
+        Technique::BugPlusOne => 5.6,
+        Technique::AlsXyWing => 5.7,
+        Technique::AIC => 6.0,
+        Technique::Nishio => 7.5,
+        Technique::CellForcingChain => 8.2,
+        Technique::DigitForcingChain => 8.4,
+    }
+}
+
+fn step_score(step: &SolveStep) -> f32 {
+    match (step.technique, step.units.first()) {
+        (Technique::HiddenSingle, Some(Unit::Block(_))) => 1.2,
+        (technique, _) => technique_score(technique),
+    }
+}
+
+pub fn rate_problem(problem: &Grid) -> Rating {
+    rate_with_options(problem, &SolverOptions::default())
+}
+
+// 一意解を前提にした手法などを使う場合は設定を渡す
+pub fn rate_with_options(problem: &Grid, options: &SolverOptions) -> Rating {
+    let trace = solve_with_options(problem, options);
+    let hardest_step = trace.steps.iter()
+        .max_by(|a, b| step_score(a).partial_cmp(&step_score(b)).unwrap());
+    let score = hardest_step.map(step_score).unwrap_or(0.0);
+
+    Rating {
+        score,
+        hardest: hardest_step.map(|x| x.technique),
+        steps: trace.steps.len(),
+        difficulty: match trace.is_solved() {
+            true => Difficulty::from_score(score),
+            false => Difficulty::Unsolvable,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOLUTION: &str = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn blank(cells: &[(usize, usize)]) -> Grid {
+        let mut grid: Grid = SOLUTION.parse().unwrap();
+        for (row_index, col_index) in cells {
+            grid.set(*row_index, *col_index, None);
+        }
+        grid
+    }
+
+    #[test]
+    fn difficulty_boundaries() {
+        let bands = [
+            (1.5, Difficulty::Easy, Difficulty::Medium),
+            (2.8, Difficulty::Medium, Difficulty::Hard),
+            (4.4, Difficulty::Hard, Difficulty::Expert),
+            (7.0, Difficulty::Expert, Difficulty::Master),
+        ];
+        for (score, at, above) in bands {
+            assert_eq!(Difficulty::from_score(score), at, "{}", score);
+            assert_eq!(Difficulty::from_score(score + 0.1), above, "{}", score);
+        }
+        assert_eq!(Difficulty::from_score(0.0), Difficulty::Easy);
+    }
+
+    #[test]
+    fn hidden_single_in_block() {
+        let rating = rate_problem(&blank(&[(0, 0)]));
+        assert_eq!(rating.score, 1.2);
+        assert_eq!(rating.hardest, Some(Technique::HiddenSingle));
+        assert_eq!(rating.steps, 1);
+        assert_eq!(rating.difficulty, Difficulty::Easy);
+    }
+
+    #[test]
+    fn score_uses_hardest_step_only() {
+        let one = rate_problem(&blank(&[(0, 0)]));
+        let many = rate_problem(&blank(&[(0, 0), (2, 4), (4, 6), (6, 1), (8, 8)]));
+        assert!(many.steps > one.steps);
+        assert_eq!(many.score, one.score);
+        assert_eq!(many.difficulty, one.difficulty);
+    }
+
+    #[test]
+    fn stuck_solver_is_unsolvable() {
+        // 1と3を入れ替えても解になる長方形で、どの手法も進めない
+        let rating = rate_problem(&blank(&[(3, 5), (3, 8), (4, 5), (4, 8)]));
+        assert_eq!(rating.difficulty, Difficulty::Unsolvable);
+        assert_eq!(rating.steps, 0);
+        assert_eq!(rating.hardest, None);
+    }
+}
@@ -0,0 +1,554 @@
+use std::fmt;
+
+use crate::candidate_grid::CandidateGrid;
+use crate::grid::{Cell, Digit, Grid, Unit};
+use crate::techniques;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Technique {
+    HiddenSingle,
+    NakedSingle,
+    Pointing,
+    BoxLineReduction,
+    NakedPair,
+    XWing,
+    Skyscraper,
+    TwoStringKite,
+    HiddenPair,
+    EmptyRectangle,
+    FinnedXWing,
+    SashimiXWing,
+    NakedTriple,
+    Swordfish,
+    HiddenTriple,
+    FinnedSwordfish,
+    SashimiSwordfish,
+    XYWing,
+    XYZWing,
+    WWing,
+    UniqueRectangleType1,
+    AvoidableRectangle,
+    SimpleColoring,
+    UniqueRectangleType2,
+    UniqueRectangleType3,
+    UniqueRectangleType4,
+    UniqueRectangleType5,
+    UniqueRectangleType6,
+    MultiColoring,
+    XChain,
+    NakedQuad,
+    XYChain,
+    SueDeCoq,
+    Jellyfish,
+    HiddenQuad,
+    FinnedJellyfish,
+    AlsXz,
+    SashimiJellyfish,
+    BugPlusOne,
+    AlsXyWing,
+    AIC,
+    Nishio,
+    CellForcingChain,
+    DigitForcingChain,
+}
+
+impl Technique {
+    // 簡単なものから順に試す
+    pub fn all() -> &'static [Technique] {
+        &[
+            Technique::HiddenSingle,
+            Technique::NakedSingle,
+            Technique::Pointing,
+            Technique::BoxLineReduction,
+            Technique::NakedPair,
+            Technique::XWing,
+            Technique::Skyscraper,
+            Technique::TwoStringKite,
+            Technique::HiddenPair,
+            Technique::EmptyRectangle,
+            Technique::FinnedXWing,
+            Technique::SashimiXWing,
+            Technique::NakedTriple,
+            Technique::Swordfish,
+            Technique::HiddenTriple,
+            Technique::FinnedSwordfish,
+            Technique::SashimiSwordfish,
+            Technique::XYWing,
+            Technique::XYZWing,
+            Technique::WWing,
+            Technique::UniqueRectangleType1,
+            Technique::AvoidableRectangle,
+            Technique::SimpleColoring,
+            Technique::UniqueRectangleType2,
+            Technique::UniqueRectangleType3,
+            Technique::UniqueRectangleType4,
+            Technique::UniqueRectangleType5,
+            Technique::UniqueRectangleType6,
+            Technique::MultiColoring,
+            Technique::XChain,
+            Technique::NakedQuad,
+            Technique::XYChain,
+            Technique::SueDeCoq,
+            Technique::Jellyfish,
+            Technique::HiddenQuad,
+            Technique::FinnedJellyfish,
+            Technique::AlsXz,
+            Technique::SashimiJellyfish,
+            Technique::BugPlusOne,
+            Technique::AlsXyWing,
+            Technique::AIC,
+            Technique::Nishio,
+            Technique::CellForcingChain,
+            Technique::DigitForcingChain,
+        ]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::HiddenSingle => "Hidden Single",
+            Technique::NakedSingle => "Naked Single",
+            Technique::Pointing => "Pointing",
+            Technique::BoxLineReduction => "Box/Line Reduction",
+            Technique::NakedPair => "Naked Pair",
+            Technique::XWing => "X-Wing",
+            Technique::HiddenPair => "Hidden Pair",
+            Technique::FinnedXWing => "Finned X-Wing",
+            Technique::SashimiXWing => "Sashimi X-Wing",
+            Technique::NakedTriple => "Naked Triple",
+            Technique::Swordfish => "Swordfish",
+            Technique::HiddenTriple => "Hidden Triple",
+            Technique::FinnedSwordfish => "Finned Swordfish",
+            Technique::Skyscraper => "Skyscraper",
+            Technique::SashimiSwordfish => "Sashimi Swordfish",
+            Technique::TwoStringKite => "2-String Kite",
+            Technique::EmptyRectangle => "Empty Rectangle",
+            Technique::XYWing => "XY-Wing",
+            Technique::XYZWing => "XYZ-Wing",
+            Technique::WWing => "W-Wing",
+            Technique::UniqueRectangleType1 => "Unique Rectangle Type 1",
+            Technique::AvoidableRectangle => "Avoidable Rectangle",
+            Technique::SimpleColoring => "Simple Coloring",
+            Technique::UniqueRectangleType2 => "Unique Rectangle Type 2",
+            Technique::UniqueRectangleType3 => "Unique Rectangle Type 3",
+            Technique::UniqueRectangleType4 => "Unique Rectangle Type 4",
+            Technique::UniqueRectangleType5 => "Unique Rectangle Type 5",
+            Technique::UniqueRectangleType6 => "Unique Rectangle Type 6",
+            Technique::MultiColoring => "Multi-Coloring",
+            Technique::XChain => "X-Chain",
+            Technique::NakedQuad => "Naked Quad",
+            Technique::XYChain => "XY-Chain",
+            Technique::SueDeCoq => "Sue de Coq",
+            Technique::Jellyfish => "Jellyfish",
+            Technique::HiddenQuad => "Hidden Quad",
+            Technique::FinnedJellyfish => "Finned Jellyfish",
+            Technique::AlsXz => "ALS-XZ",
+            Technique::SashimiJellyfish => "Sashimi Jellyfish",
+            Technique::BugPlusOne => "BUG+1",
+            Technique::AlsXyWing => "ALS-XY-Wing",
+            Technique::AIC => "AIC",
+            Technique::Nishio => "Nishio",
+            Technique::CellForcingChain => "Cell Forcing Chain",
+            Technique::DigitForcingChain => "Digit Forcing Chain",
+        }
+    }
+
+    // 解が一意であることを前提にした手法
+    pub fn requires_uniqueness(self) -> bool {
+        matches!(
+            self,
+            Technique::UniqueRectangleType1
+                | Technique::UniqueRectangleType2
+                | Technique::UniqueRectangleType3
+                | Technique::UniqueRectangleType4
+                | Technique::UniqueRectangleType5
+                | Technique::UniqueRectangleType6
+                | Technique::AvoidableRectangle
+                | Technique::BugPlusOne
+        )
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Candidate {
+    pub cell: Cell,
+    pub digit: Digit,
+}
+
+// 数字とマスを続けて 5r1c1 の形式で表示する
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.digit, self.cell)
+    }
+}
+
+// 強リンクはどちらか一方が必ず真、弱リンクは両方が真になることはない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkKind {
+    Strong,
+    Weak,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Link {
+    pub from: Candidate,
+    pub to: Candidate,
+    pub kind: LinkKind,
+}
+
+impl Link {
+    pub fn strong(from: Candidate, to: Candidate) -> Link {
+        Link { from, to, kind: LinkKind::Strong }
+    }
+
+    pub fn weak(from: Candidate, to: Candidate) -> Link {
+        Link { from, to, kind: LinkKind::Weak }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepAction {
+    Place(Cell, Digit),
+    Eliminate(Vec<Candidate>),
+}
+
+// 手法ごとの補足情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepDetail {
+    None,
+    // 数字digitについて、baseのユニットの候補がcoverのユニットに収まる。finsはcoverからはみ出た候補
+    Fish {
+        digit: Digit,
+        base: Vec<Unit>,
+        cover: Vec<Unit>,
+        fins: Vec<Cell>,
+    },
+    // 軸になるマスと、除外される数字を挟むマス。W-Wingでは強リンクの2マスが軸
+    Wing {
+        pivots: Vec<Cell>,
+        pincers: Vec<Cell>,
+    },
+    // 数字digitの強リンクでつながったマスを2色に塗り分けたもの。同じ色のマスは真偽が揃う
+    Coloring {
+        digit: Digit,
+        clusters: Vec<(Vec<Cell>, Vec<Cell>)>,
+    },
+    // ALS (n個のマスにn+1個の候補しかない集合) の組と、それらをつなぐ制限付き共通候補
+    AlmostLockedSets {
+        sets: Vec<Vec<Cell>>,
+        restricted: Vec<Digit>,
+    },
+    // 分岐ごとの、仮定から結論までに導いた配置の列と、その分岐が矛盾したか。
+    // depthはシングルを何巡適用して結論に至ったか
+    Forcing {
+        depth: usize,
+        branches: Vec<(Vec<Candidate>, bool)>,
+    },
+    // デッドリーパターンになりうる長方形の2つの数字と4マス。
+    // floorは2つの数字だけを持つマス、roofはそれ以外も持つマス。Avoidable Rectangleでは確定済みのマスがfloor
+    Rectangle {
+        digits: Vec<Digit>,
+        corners: Vec<Cell>,
+        floor: Vec<Cell>,
+        roof: Vec<Cell>,
+    },
+    // パターンを構成するリンク。つながっているリンクは連鎖として表示する
+    Links {
+        links: Vec<Link>,
+    },
+}
+
+impl fmt::Display for StepDetail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StepDetail::None => Ok(()),
+            StepDetail::Fish { base, cover, fins, .. } => {
+                write!(f, "base {}; cover {}", join(base), join(cover))?;
+                if !fins.is_empty() {
+                    write!(f, "; fins {}", join(fins))?;
+                }
+                Ok(())
+            }
+            StepDetail::Wing { pivots, pincers } => {
+                write!(f, "pivot {}; pincers {}", join(pivots), join(pincers))
+            }
+            StepDetail::Coloring { clusters, .. } => {
+                let texts: Vec<String> = clusters.iter()
+                    .map(|(on, off)| format!("{} | {}", join(on), join(off)))
+                    .collect();
+                write!(f, "colors {}", texts.join("; "))
+            }
+            StepDetail::AlmostLockedSets { sets, restricted } => {
+                let texts: Vec<String> = sets.iter().map(|x| join(x)).collect();
+                write!(f, "sets {}", texts.join(" | "))?;
+                if !restricted.is_empty() {
+                    write!(f, "; restricted {}", join(restricted))?;
+                }
+                Ok(())
+            }
+            StepDetail::Forcing { depth, branches } => {
+                let texts: Vec<String> = branches.iter()
+                    .map(|(path, contradiction)| {
+                        let mut texts: Vec<String> = path.iter().map(|x| x.to_string()).collect();
+                        if *contradiction {
+                            texts.push("contradiction".to_string());
+                        }
+                        texts.join(" -> ")
+                    })
+                    .collect();
+                write!(f, "{}; depth {}", texts.join(" | "), depth)
+            }
+            StepDetail::Rectangle { digits, corners, floor, roof } => {
+                write!(f, "digits {}; corners {}", join(digits), join(corners))?;
+                if !floor.is_empty() {
+                    write!(f, "; floor {}", join(floor))?;
+                }
+                if !roof.is_empty() {
+                    write!(f, "; roof {}", join(roof))?;
+                }
+                Ok(())
+            }
+            StepDetail::Links { links } => {
+                let texts: Vec<String> = chain_segments(links).iter()
+                    .map(|x| eureka(x))
+                    .collect();
+                write!(f, "{}", texts.join("; "))
+            }
+        }
+    }
+}
+
+// つながっているリンクごとに分ける
+fn chain_segments(links: &[Link]) -> Vec<&[Link]> {
+    let mut segments: Vec<&[Link]> = Vec::new();
+    let mut start = 0;
+    for i in 1..=links.len() {
+        if i == links.len() || links[i - 1].to != links[i].from {
+            segments.push(&links[start..i]);
+            start = i;
+        }
+    }
+    segments
+}
+
+// Eureka表記。同じマスの強リンクは (1=2)r1c1 のようにまとめる
+fn eureka(links: &[Link]) -> String {
+    let mut nodes: Vec<Candidate> = links.iter().map(|x| x.from).collect();
+    nodes.extend(links.last().map(|x| x.to));
+    let symbol = |kind: LinkKind| match kind {
+        LinkKind::Strong => "=",
+        LinkKind::Weak => "-",
+    };
+
+    let mut text = String::new();
+    let mut i = 0;
+    while i < nodes.len() {
+        if i > 0 {
+            text.push_str(symbol(links[i - 1].kind));
+        }
+        if i + 1 < nodes.len() && nodes[i].cell == nodes[i + 1].cell && links[i].kind == LinkKind::Strong {
+            text.push_str(&format!("({}={}){}", nodes[i].digit, nodes[i + 1].digit, nodes[i].cell));
+            i += 2;
+        } else {
+            text.push_str(&format!("({}){}", nodes[i].digit, nodes[i].cell));
+            i += 1;
+        }
+    }
+    text
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    let texts: Vec<String> = items.iter().map(|x| x.to_string()).collect();
+    texts.join(", ")
+}
+
+// 1手分の解説。cellsはパターンを構成するマス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveStep {
+    pub technique: Technique,
+    pub action: StepAction,
+    pub units: Vec<Unit>,
+    pub cells: Vec<Cell>,
+    pub detail: StepDetail,
+}
+
+impl fmt::Display for SolveStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.technique)?;
+        match &self.action {
+            StepAction::Place(cell, digit) => write!(f, "{}={}", cell, digit)?,
+            StepAction::Eliminate(candidates) => {
+                let texts: Vec<String> = candidates.iter()
+                    .map(|x| format!("{}<>{}", x.cell, x.digit))
+                    .collect();
+                write!(f, "{}", texts.join(", "))?;
+            }
+        }
+        match &self.detail {
+            StepDetail::None if self.units.is_empty() => Ok(()),
+            StepDetail::None => write!(f, " [{}]", join(&self.units)),
+            detail => write!(f, " [{}]", detail),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveTrace {
+    pub grid: Grid,
+    pub steps: Vec<SolveStep>,
+}
+
+impl SolveTrace {
+    pub fn is_solved(&self) -> bool {
+        self.grid.is_solved()
+    }
+}
+
+// 使う手法の設定。既定ではすべての手法を使う。
+// max_chain_lengthは連鎖系の手法で辿るリンクの数の上限。
+// max_forcing_depthは仮定を置く手法でシングルを適用する巡の上限。
+// 一意解を前提にした手法は、解の一意性が保証されている問題でallow_uniquenessを立てたときだけ使う
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolverOptions {
+    pub techniques: Vec<Technique>,
+    pub max_chain_length: usize,
+    pub max_forcing_depth: usize,
+    pub allow_uniqueness: bool,
+}
+
+impl SolverOptions {
+    pub fn is_enabled(&self, technique: Technique) -> bool {
+        self.techniques.contains(&technique)
+            && (self.allow_uniqueness || !technique.requires_uniqueness())
+    }
+
+    pub fn with(mut self, technique: Technique) -> SolverOptions {
+        if !self.techniques.contains(&technique) {
+            self.techniques.push(technique);
+        }
+        self
+    }
+
+    pub fn without(mut self, technique: Technique) -> SolverOptions {
+        self.techniques.retain(|x| *x != technique);
+        self
+    }
+
+    // シングルと、交差・サブセットによる候補の除外だけを使う
+    pub fn singles_and_subsets() -> SolverOptions {
+        SolverOptions {
+            techniques: vec![
+                Technique::HiddenSingle,
+                Technique::NakedSingle,
+                Technique::Pointing,
+                Technique::BoxLineReduction,
+                Technique::NakedPair,
+                Technique::HiddenPair,
+                Technique::NakedTriple,
+                Technique::HiddenTriple,
+                Technique::NakedQuad,
+                Technique::HiddenQuad,
+            ],
+            ..SolverOptions::default()
+        }
+    }
+
+    pub fn with_max_chain_length(mut self, max_chain_length: usize) -> SolverOptions {
+        self.max_chain_length = max_chain_length;
+        self
+    }
+
+    pub fn with_max_forcing_depth(mut self, max_forcing_depth: usize) -> SolverOptions {
+        self.max_forcing_depth = max_forcing_depth;
+        self
+    }
+
+    pub fn with_uniqueness(mut self, allow_uniqueness: bool) -> SolverOptions {
+        self.allow_uniqueness = allow_uniqueness;
+        self
+    }
+}
+
+impl Default for SolverOptions {
+    fn default() -> SolverOptions {
+        SolverOptions {
+            techniques: Technique::all().to_vec(),
+            max_chain_length: 12,
+            max_forcing_depth: 8,
+            allow_uniqueness: false,
+        }
+    }
+}
+
+// 論理的な解法だけで1手ずつ進め、その手順を記録する。解けない場合は途中の盤面を返す
+pub fn solve_with_trace(problem: &Grid) -> SolveTrace {
+    solve_with_options(problem, &SolverOptions::default())
+}
+
+// 有効な手法だけを簡単なものから順に試す
+pub fn solve_with_options(problem: &Grid, options: &SolverOptions) -> SolveTrace {
+    let mut candidates = CandidateGrid::new(problem);
+    let mut steps: Vec<SolveStep> = Vec::new();
+
+    while candidates.grid().count_empty() > 0 && !candidates.has_contradiction() {
+        let step = Technique::all().iter()
+            .filter(|x| options.is_enabled(**x))
+            .find_map(|x| techniques::find_step(*x, &candidates, options));
+        match step {
+            Some(step) => {
+                candidates.apply(&step);
+                steps.push(step);
+            }
+            None => break,
+        }
+    }
+
+    SolveTrace {
+        grid: *candidates.grid(),
+        steps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(digit: u8, row: usize, col: usize) -> Candidate {
+        Candidate { cell: Cell::new(row - 1, col - 1), digit: Digit::new(digit).unwrap() }
+    }
+
+    #[test]
+    fn eureka_notation() {
+        // 同じマスの強リンクは1つにまとめる
+        let links = vec![
+            Link::strong(candidate(3, 5, 1), candidate(9, 5, 1)),
+            Link::weak(candidate(9, 5, 1), candidate(9, 5, 6)),
+            Link::strong(candidate(9, 5, 6), candidate(3, 5, 6)),
+            Link::weak(candidate(3, 5, 6), candidate(3, 2, 6)),
+            Link::strong(candidate(3, 2, 6), candidate(3, 2, 9)),
+        ];
+        let detail = StepDetail::Links { links };
+        assert_eq!(detail.to_string(), "(3=9)r5c1-(9=3)r5c6-(3)r2c6=(3)r2c9");
+
+        // つながっていないリンクは別の連鎖として区切る
+        let links = vec![
+            Link::strong(candidate(6, 3, 8), candidate(6, 3, 2)),
+            Link::weak(candidate(6, 3, 2), candidate(6, 9, 2)),
+            Link::strong(candidate(6, 9, 2), candidate(6, 9, 7)),
+            Link::strong(candidate(5, 7, 5), candidate(5, 2, 5)),
+        ];
+        let step = SolveStep {
+            technique: Technique::Skyscraper,
+            action: StepAction::Eliminate(vec![candidate(6, 1, 7), candidate(6, 7, 8)]),
+            units: Vec::new(),
+            cells: Vec::new(),
+            detail: StepDetail::Links { links },
+        };
+        assert_eq!(step.to_string(), "Skyscraper: r1c7<>6, r7c8<>6 [(6)r3c8=(6)r3c2-(6)r9c2=(6)r9c7; (5)r7c5=(5)r2c5]");
+    }
+}
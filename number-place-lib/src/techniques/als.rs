@@ -0,0 +1,271 @@
+use crate::candidate_grid::{digit_bit, mask_digits, CandidateGrid};
+use crate::grid::{Cell, Digit, Unit, SIZE};
+use crate::solver::{SolveStep, StepDetail, Technique};
+
+use super::{combinations, elimination_step};
+
+// ALS (Almost Locked Set): 1つのユニット内のn個の空きマスで、候補がちょうどn+1個のもの
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Als {
+    cells: Vec<Cell>,
+    mask: u16,
+}
+
+impl Als {
+    fn overlaps(&self, other: &Als) -> bool {
+        self.cells.iter().any(|x| other.cells.contains(x))
+    }
+
+    // ALS内で数字digitを候補に持つマス
+    fn cells_with(&self, candidates: &CandidateGrid, digit: Digit) -> Vec<Cell> {
+        self.cells.iter().filter(|x| candidates.has(**x, digit)).copied().collect()
+    }
+}
+
+// すべてのユニットからALSを集める。行とブロックの両方に収まるものは1つにまとめる
+fn find_als(candidates: &CandidateGrid) -> Vec<Als> {
+    let mut sets: Vec<Als> = Vec::new();
+    for unit in Unit::all() {
+        let empty_cells: Vec<Cell> = unit.cells().filter(|x| candidates.is_empty_cell(*x)).collect();
+        for size in 1..empty_cells.len() {
+            for mut cells in combinations(&empty_cells, size) {
+                let mask = cells.iter().fold(0, |acc, x| acc | candidates.mask(*x));
+                if mask.count_ones() as usize != size + 1 {
+                    continue;
+                }
+                cells.sort();
+                sets.push(Als { cells, mask });
+            }
+        }
+    }
+    sets.sort_by(|x, y| x.cells.cmp(&y.cells));
+    sets.dedup();
+    sets
+}
+
+// 2つのALSの制限付き共通候補 (RCC): 両方にあり、その候補を持つマスがすべて互いに見えている数字。
+// 両方のALSで同時に真になれないので、どちらかのALSは残りの数字で確定する
+fn restricted_commons(candidates: &CandidateGrid, als1: &Als, als2: &Als) -> Vec<Digit> {
+    mask_digits(als1.mask & als2.mask)
+        .filter(|x| {
+            let cells2 = als2.cells_with(candidates, *x);
+            als1.cells_with(candidates, *x).iter().all(|cell| cells2.iter().all(|y| cell.sees(*y)))
+        })
+        .collect()
+}
+
+// 両端のALSに共通する数字zは、どちらかのALSに必ず入るので、
+// 両方のzを持つマスすべてから見えるマスのzを除外する
+fn common_eliminations(candidates: &CandidateGrid, als1: &Als, als2: &Als, excluded: u16) -> Vec<(Cell, Digit)> {
+    let mut eliminations: Vec<(Cell, Digit)> = Vec::new();
+    for digit in mask_digits(als1.mask & als2.mask & !excluded) {
+        let mut cells = als1.cells_with(candidates, digit);
+        cells.extend(als2.cells_with(candidates, digit));
+        eliminations.extend(Cell::all()
+            .filter(|x| !als1.cells.contains(x) && !als2.cells.contains(x))
+            .filter(|x| cells.iter().all(|y| x.sees(*y)))
+            .map(|x| (x, digit)));
+    }
+    eliminations
+}
+
+fn als_step(
+    technique: Technique,
+    candidates: &CandidateGrid,
+    eliminations: Vec<(Cell, Digit)>,
+    sets: &[&Als],
+    restricted: Vec<Digit>,
+) -> Option<SolveStep> {
+    let cells: Vec<Cell> = sets.iter().flat_map(|x| x.cells.iter().copied()).collect();
+    elimination_step(technique, candidates, eliminations.into_iter(), Vec::new(), cells)
+        .map(|step| SolveStep {
+            detail: StepDetail::AlmostLockedSets {
+                sets: sets.iter().map(|x| x.cells.clone()).collect(),
+                restricted,
+            },
+            ..step
+        })
+}
+
+// 重ならない2つのALSがRCCのxで結ばれていれば、x以外の共通の数字zはどちらかのALSに入る
+pub(super) fn find_als_xz(candidates: &CandidateGrid) -> Option<SolveStep> {
+    let sets = find_als(candidates);
+    for (i, als1) in sets.iter().enumerate() {
+        for als2 in sets.iter().skip(i + 1) {
+            if als1.mask & als2.mask == 0 || als1.overlaps(als2) {
+                continue;
+            }
+            for x in restricted_commons(candidates, als1, als2) {
+                let eliminations = common_eliminations(candidates, als1, als2, digit_bit(x));
+                let step = als_step(Technique::AlsXz, candidates, eliminations, &[als1, als2], vec![x]);
+                if step.is_some() {
+                    return step;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// 軸のALSがRCCのxとyで2つのALSに結ばれていれば、xとyのどちらかは軸に入らないので、
+// 両端のALSのどちらかは確定する。両端に共通する数字z (x, y以外) を除外できる
+pub(super) fn find_als_xy_wing(candidates: &CandidateGrid) -> Option<SolveStep> {
+    let sets = find_als(candidates);
+    // RCCでつながる重ならないALSの組
+    let mut links: Vec<Vec<(usize, Digit)>> = vec![Vec::new(); sets.len()];
+    for (i, als1) in sets.iter().enumerate() {
+        for (j, als2) in sets.iter().enumerate().skip(i + 1) {
+            if als1.mask & als2.mask == 0 || als1.overlaps(als2) {
+                continue;
+            }
+            for x in restricted_commons(candidates, als1, als2) {
+                links[i].push((j, x));
+                links[j].push((i, x));
+            }
+        }
+    }
+
+    for (pivot, pivot_links) in links.iter().enumerate() {
+        for (k, (wing1, x)) in pivot_links.iter().enumerate() {
+            for (wing2, y) in pivot_links.iter().skip(k + 1) {
+                let (als1, als2) = (&sets[*wing1], &sets[*wing2]);
+                if x == y || wing1 == wing2 || als1.overlaps(als2) {
+                    continue;
+                }
+                let eliminations = common_eliminations(candidates, als1, als2, digit_bit(*x) | digit_bit(*y));
+                let step = als_step(Technique::AlsXyWing, candidates, eliminations, &[als1, &sets[pivot], als2], vec![*x, *y]);
+                if step.is_some() {
+                    return step;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// 行(列)とブロックの交差の2～3マスが、その数+2個以上の候補を持つとき、
+// 行の残りのマスと、ブロックの残りのマスから、交差の候補を分け合う候補を互いに重ならないように選ぶ。
+// マスの数と数字の数が等しければすべてのマスに異なる数字が入るので、
+// 行側のマスの候補と、ブロック側に無い交差の候補は行の他のマスから除外できる (ブロックも同様)
+pub(super) fn find_sue_de_coq(candidates: &CandidateGrid) -> Option<SolveStep> {
+    for block_index in 0..SIZE {
+        let block = Unit::Block(block_index);
+        let origin = block.cell_at(0);
+        let lines = (0..3).map(|x| Unit::Row(origin.row_index + x))
+            .chain((0..3).map(|x| Unit::Col(origin.col_index + x)));
+        for line in lines {
+            let intersection: Vec<Cell> = line.cells()
+                .filter(|x| block.contains(*x) && candidates.is_empty_cell(*x))
+                .collect();
+            let line_cells: Vec<Cell> = line.cells()
+                .filter(|x| !block.contains(*x) && candidates.is_empty_cell(*x))
+                .collect();
+            let block_cells: Vec<Cell> = block.cells()
+                .filter(|x| !line.contains(*x) && candidates.is_empty_cell(*x))
+                .collect();
+
+            for size in 2..=intersection.len() {
+                for core in combinations(&intersection, size) {
+                    let core_mask = core.iter().fold(0, |acc, x| acc | candidates.mask(*x));
+                    if (core_mask.count_ones() as usize) < size + 2 {
+                        continue;
+                    }
+                    let step = sue_de_coq_step(candidates, (line, &line_cells), (block, &block_cells), &core, core_mask);
+                    if step.is_some() {
+                        return step;
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn sue_de_coq_step(
+    candidates: &CandidateGrid,
+    (line, line_cells): (Unit, &[Cell]),
+    (block, block_cells): (Unit, &[Cell]),
+    core: &[Cell],
+    core_mask: u16,
+) -> Option<SolveStep> {
+    let union_mask = |cells: &[Cell]| cells.iter().fold(0, |acc, x| acc | candidates.mask(*x));
+    for line_size in 1..=line_cells.len().min(3) {
+        for line_set in combinations(line_cells, line_size) {
+            let line_mask = union_mask(&line_set);
+            if line_mask & core_mask == 0 {
+                continue;
+            }
+            for block_size in 1..=block_cells.len().min(3) {
+                for block_set in combinations(block_cells, block_size) {
+                    let block_mask = union_mask(&block_set);
+                    let total = core.len() + line_size + block_size;
+                    let is_pattern = block_mask & core_mask != 0
+                        && line_mask & block_mask == 0
+                        && (core_mask | line_mask | block_mask).count_ones() as usize == total;
+                    if !is_pattern {
+                        continue;
+                    }
+
+                    let line_digits = line_mask | (core_mask & !block_mask);
+                    let block_digits = block_mask | (core_mask & !line_mask);
+                    let line_targets = line.cells()
+                        .filter(|x| !core.contains(x) && !line_set.contains(x))
+                        .flat_map(|x| mask_digits(line_digits).map(move |digit| (x, digit)));
+                    let block_targets = block.cells()
+                        .filter(|x| !core.contains(x) && !block_set.contains(x))
+                        .flat_map(|x| mask_digits(block_digits).map(move |digit| (x, digit)));
+                    let cells: Vec<Cell> = core.iter().chain(line_set.iter()).chain(block_set.iter()).copied().collect();
+                    let step = elimination_step(Technique::SueDeCoq, candidates, line_targets.chain(block_targets), vec![line, block], cells)
+                        .map(|step| SolveStep {
+                            detail: StepDetail::AlmostLockedSets {
+                                sets: vec![core.to_vec(), line_set.clone(), block_set.clone()],
+                                restricted: Vec::new(),
+                            },
+                            ..step
+                        });
+                    if step.is_some() {
+                        return step;
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Technique;
+    use crate::techniques::assert_first_step;
+
+    #[test]
+    fn als_xz() {
+        assert_first_step(
+            ".9...62..3..5...69......58.9.....3.....2.....86..1.......7.......7..1.34.1..84..7",
+            Technique::AlsXz,
+            "ALS-XZ: r7c1<>5 [sets r1c1, r2c2, r3c1, r3c2, r3c3 | r8c1, r8c2, r9c1; restricted 8]",
+        );
+    }
+
+    #[test]
+    fn als_xy_wing() {
+        assert_first_step(
+            "..52......3...1.......7.49.8....7...2.43..15.....1.8....61...7.59...6.4.....42...",
+            Technique::AlsXyWing,
+            "ALS-XY-Wing: r7c1<>3 [sets r7c5, r7c6, r8c4, r9c4 | r8c5, r8c7 | r8c3, r9c1, r9c2, r9c3; restricted 8, 2]",
+        );
+    }
+
+    #[test]
+    fn sue_de_coq() {
+        assert_first_step(
+            "..3....6.....43...8....6...........4.2..17...5.6.84.1..5..326..7.....29.6..1..8..",
+            Technique::SueDeCoq,
+            "Sue de Coq: r2c3<>1, r3c3<>1, r3c3<>4, r5c1<>9 [sets r4c3, r5c3 | r8c3 | r6c2]",
+        );
+    }
+}
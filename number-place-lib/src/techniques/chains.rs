@@ -0,0 +1,222 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::candidate_grid::CandidateGrid;
+use crate::grid::{Cell, Digit};
+use crate::solver::{Candidate, Link, SolveStep, StepDetail, Technique};
+
+use super::elimination_step;
+
+// 連鎖に使えるリンクの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ChainKind {
+    // 1つの数字の強リンク (ユニット内の2マス) だけを使う
+    X,
+    // 2択マスの強リンクと、マス間の同じ数字の弱リンクだけを使う
+    XY,
+    // 両方を混ぜて使う
+    Alternating,
+}
+
+impl ChainKind {
+    fn technique(self) -> Technique {
+        match self {
+            ChainKind::X => Technique::XChain,
+            ChainKind::XY => Technique::XYChain,
+            ChainKind::Alternating => Technique::AIC,
+        }
+    }
+
+    fn uses_bilocal(self) -> bool {
+        self != ChainKind::XY
+    }
+
+    fn uses_bivalue(self) -> bool {
+        self != ChainKind::X
+    }
+}
+
+fn candidate(cell: Cell, digit: Digit) -> Candidate {
+    Candidate { cell, digit }
+}
+
+// どちらか一方が必ず真になる候補
+fn strong_neighbors(candidates: &CandidateGrid, node: Candidate, kind: ChainKind) -> Vec<Candidate> {
+    let mut nodes: Vec<Candidate> = Vec::new();
+    if kind.uses_bilocal() {
+        nodes.extend(node.cell.units().iter()
+            .filter(|x| candidates.positions(**x, node.digit).count_ones() == 2)
+            .flat_map(|x| candidates.cells_with(*x, node.digit))
+            .filter(|x| *x != node.cell)
+            .map(|x| candidate(x, node.digit)));
+    }
+    if kind.uses_bivalue() && candidates.count(node.cell) == 2 {
+        nodes.extend(candidates.digits(node.cell)
+            .filter(|x| *x != node.digit)
+            .map(|x| candidate(node.cell, x)));
+    }
+    nodes.sort();
+    nodes.dedup();
+    nodes
+}
+
+// 両方が真になることはない候補
+fn weak_neighbors(candidates: &CandidateGrid, node: Candidate, kind: ChainKind) -> Vec<Candidate> {
+    let mut nodes: Vec<Candidate> = Cell::all()
+        .filter(|x| node.cell.sees(*x) && candidates.has(*x, node.digit))
+        .map(|x| candidate(x, node.digit))
+        .collect();
+    if kind == ChainKind::Alternating {
+        nodes.extend(candidates.digits(node.cell)
+            .filter(|x| *x != node.digit)
+            .map(|x| candidate(node.cell, x)));
+    }
+    nodes
+}
+
+// 両端のどちらかが真になるときに除外できる候補
+fn chain_eliminations(candidates: &CandidateGrid, start: Candidate, end: Candidate) -> Vec<(Cell, Digit)> {
+    if start.digit == end.digit {
+        Cell::all()
+            .filter(|x| x.sees(start.cell) && x.sees(end.cell))
+            .map(|x| (x, start.digit))
+            .collect()
+    } else if start.cell == end.cell {
+        candidates.digits(start.cell)
+            .filter(|x| *x != start.digit && *x != end.digit)
+            .map(|x| (start.cell, x))
+            .collect()
+    } else if start.cell.sees(end.cell) {
+        vec![(start.cell, end.digit), (end.cell, start.digit)]
+    } else {
+        Vec::new()
+    }
+}
+
+// startから強リンクで始まり強リンクで終わる最短の連鎖を探す。
+// 連鎖の途中は(候補, 直前が強リンクか)の状態で幅優先に辿る
+fn chain_from(candidates: &CandidateGrid, start: Candidate, kind: ChainKind, max_length: usize) -> Option<SolveStep> {
+    let mut parents: HashMap<(Candidate, bool), (Candidate, bool)> = HashMap::new();
+    let mut queue: VecDeque<((Candidate, bool), usize)> = VecDeque::new();
+    parents.insert((start, false), (start, false));
+    queue.push_back(((start, false), 0));
+
+    while let Some(((node, after_strong), length)) = queue.pop_front() {
+        if after_strong && node != start && length >= 3 {
+            let eliminations = chain_eliminations(candidates, start, node);
+            let links = chain_links(&parents, (node, after_strong));
+            let mut cells: Vec<Cell> = links.iter().map(|x| x.from.cell).chain(std::iter::once(node.cell)).collect();
+            cells.dedup();
+            let step = elimination_step(kind.technique(), candidates, eliminations.into_iter(), Vec::new(), cells)
+                .map(|step| SolveStep {
+                    detail: StepDetail::Links { links },
+                    ..step
+                });
+            if step.is_some() {
+                return step;
+            }
+        }
+        if length >= max_length {
+            continue;
+        }
+
+        let next_nodes = match after_strong {
+            true => weak_neighbors(candidates, node, kind),
+            false => strong_neighbors(candidates, node, kind),
+        };
+        for next in next_nodes {
+            let state = (next, !after_strong);
+            if parents.contains_key(&state) {
+                continue;
+            }
+            parents.insert(state, (node, after_strong));
+            queue.push_back((state, length + 1));
+        }
+    }
+
+    None
+}
+
+// 終端の状態から親を辿ってリンクの列に戻す
+fn chain_links(parents: &HashMap<(Candidate, bool), (Candidate, bool)>, end: (Candidate, bool)) -> Vec<Link> {
+    let mut links: Vec<Link> = Vec::new();
+    let mut state = end;
+    while let Some(parent) = parents.get(&state).copied() {
+        if parent == state {
+            break;
+        }
+        links.push(match state.1 {
+            true => Link::strong(parent.0, state.0),
+            false => Link::weak(parent.0, state.0),
+        });
+        state = parent;
+    }
+    links.reverse();
+    links
+}
+
+// 強リンクと弱リンクを交互にたどる連鎖。両端のどちらかは必ず真になる。
+// すべての候補から探し、リンクの最も少ない連鎖を採用する
+pub(super) fn find_chain(candidates: &CandidateGrid, kind: ChainKind, max_length: usize) -> Option<SolveStep> {
+    let starts = Cell::all()
+        .filter(|x| kind != ChainKind::XY || candidates.count(*x) == 2)
+        .flat_map(|x| candidates.digits(x).map(move |digit| candidate(x, digit)));
+
+    let mut best: Option<(usize, SolveStep)> = None;
+    for start in starts {
+        if let Some(step) = chain_from(candidates, start, kind, max_length) {
+            let length = match &step.detail {
+                StepDetail::Links { links } => links.len(),
+                _ => 0,
+            };
+            if best.as_ref().is_none_or(|x| length < x.0) {
+                best = Some((length, step));
+            }
+        }
+    }
+
+    best.map(|x| x.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::SolverOptions;
+    use crate::techniques::{assert_first_step, first_step};
+
+    const X_CHAIN: &str = "..8...4.....26..89...........35..9.7.1.3.....5....2..46.78......349..7..2....73..";
+
+    #[test]
+    fn x_chain() {
+        assert_first_step(
+            X_CHAIN,
+            Technique::XChain,
+            "X-Chain: r8c8<>1 [(1)r4c8=(1)r6c7-(1)r2c7=(1)r2c3-(1)r9c3=(1)r8c1]",
+        );
+    }
+
+    #[test]
+    fn xy_chain() {
+        assert_first_step(
+            "...2......6...534......31.6......9...45....671.8.6...5.5.9..6..4........7...218..",
+            Technique::XYChain,
+            "XY-Chain: r4c2<>3, r7c1<>3 [(3=9)r5c1-(9=8)r5c6-(8=7)r8c6-(7=5)r8c7-(5=9)r9c8-(9=3)r9c2]",
+        );
+    }
+
+    #[test]
+    fn aic() {
+        assert_first_step(
+            "..8...4.....26..89...........35..9.7.1.3.....5....2..46.78......349..7..2....73..",
+            Technique::AIC,
+            "AIC: r7c5<>4 [(3)r7c5=(3)r7c6-(3=4)r2c6-(4)r3c4=(4)r9c4]",
+        );
+    }
+
+    // 5本のリンクの連鎖は、上限が4本なら見つからない
+    #[test]
+    fn max_chain_length_limits_chains() {
+        let (candidates, step) = first_step(X_CHAIN, Technique::XChain, &SolverOptions::default());
+        assert_eq!(find_chain(&candidates, ChainKind::X, 5), Some(step));
+        assert_eq!(find_chain(&candidates, ChainKind::X, 4), None);
+    }
+}
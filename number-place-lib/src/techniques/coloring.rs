@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+
+use crate::candidate_grid::CandidateGrid;
+use crate::grid::{Cell, Digit, CELL_COUNT};
+use crate::solver::{SolveStep, StepDetail, Technique};
+
+use super::elimination_step;
+
+type Cluster = (Vec<Cell>, Vec<Cell>);
+
+// 強リンクでつながったマスを交互に2色に塗る。塗り分けられないものは矛盾を含むので除く
+fn clusters(candidates: &CandidateGrid, digit: Digit) -> Vec<Cluster> {
+    let mut neighbors: Vec<Vec<Cell>> = vec![Vec::new(); CELL_COUNT];
+    for (_, cell1, cell2) in candidates.strong_links(digit) {
+        neighbors[cell1.index()].push(cell2);
+        neighbors[cell2.index()].push(cell1);
+    }
+
+    let mut colors: [Option<bool>; CELL_COUNT] = [None; CELL_COUNT];
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for start in Cell::all() {
+        if neighbors[start.index()].is_empty() || colors[start.index()].is_some() {
+            continue;
+        }
+
+        let mut cluster: Cluster = (Vec::new(), Vec::new());
+        let mut is_consistent = true;
+        let mut queue: VecDeque<Cell> = VecDeque::new();
+        colors[start.index()] = Some(true);
+        queue.push_back(start);
+        while let Some(cell) = queue.pop_front() {
+            let color = colors[cell.index()] == Some(true);
+            match color {
+                true => cluster.0.push(cell),
+                false => cluster.1.push(cell),
+            }
+            for neighbor in neighbors[cell.index()].iter() {
+                match colors[neighbor.index()] {
+                    None => {
+                        colors[neighbor.index()] = Some(!color);
+                        queue.push_back(*neighbor);
+                    }
+                    Some(x) if x == color => is_consistent = false,
+                    Some(_) => {}
+                }
+            }
+        }
+
+        if is_consistent {
+            cluster.0.sort();
+            cluster.1.sort();
+            clusters.push(cluster);
+        }
+    }
+
+    clusters
+}
+
+fn sees_any(cell: Cell, cells: &[Cell]) -> bool {
+    cells.iter().any(|x| cell.sees(*x))
+}
+
+fn coloring_step(
+    technique: Technique,
+    candidates: &CandidateGrid,
+    digit: Digit,
+    eliminations: Vec<Cell>,
+    clusters: Vec<Cluster>,
+) -> Option<SolveStep> {
+    let cells: Vec<Cell> = clusters.iter()
+        .flat_map(|(on, off)| on.iter().chain(off.iter()).copied())
+        .collect();
+    let eliminations = eliminations.into_iter().map(|x| (x, digit));
+    elimination_step(technique, candidates, eliminations, Vec::new(), cells)
+        .map(|step| SolveStep {
+            detail: StepDetail::Coloring { digit, clusters },
+            ..step
+        })
+}
+
+// 同じ色どうしが見えていればその色は偽、両方の色から見えるマスは偽
+pub(super) fn find_simple_coloring(candidates: &CandidateGrid) -> Option<SolveStep> {
+    for digit in Digit::all() {
+        for cluster in clusters(candidates, digit) {
+            let (on, off) = &cluster;
+            for color in [on, off].iter() {
+                if color.iter().any(|x| sees_any(*x, color)) {
+                    let step = coloring_step(Technique::SimpleColoring, candidates, digit, color.to_vec(), vec![cluster.clone()]);
+                    if step.is_some() {
+                        return step;
+                    }
+                }
+            }
+
+            let trapped: Vec<Cell> = Cell::all()
+                .filter(|x| sees_any(*x, on) && sees_any(*x, off))
+                .collect();
+            let step = coloring_step(Technique::SimpleColoring, candidates, digit, trapped, vec![cluster.clone()]);
+            if step.is_some() {
+                return step;
+            }
+        }
+    }
+
+    None
+}
+
+// 2つの塗り分けの色aと色bが見えていれば、aの反対色とbの反対色のどちらかは真になる。
+// また、ある色から相手の両方の色が見えていれば、その色は偽
+pub(super) fn find_multi_coloring(candidates: &CandidateGrid) -> Option<SolveStep> {
+    for digit in Digit::all() {
+        let clusters = clusters(candidates, digit);
+        for (i, cluster1) in clusters.iter().enumerate() {
+            for (j, cluster2) in clusters.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+
+                let colors1 = [(&cluster1.0, &cluster1.1), (&cluster1.1, &cluster1.0)];
+                let colors2 = [(&cluster2.0, &cluster2.1), (&cluster2.1, &cluster2.0)];
+                for (color1, opposite1) in colors1.iter() {
+                    let seen: Vec<bool> = colors2.iter()
+                        .map(|(color2, _)| color1.iter().any(|x| sees_any(*x, color2)))
+                        .collect();
+                    if seen.iter().all(|x| *x) {
+                        let step = coloring_step(Technique::MultiColoring, candidates, digit, color1.to_vec(), vec![cluster1.clone(), cluster2.clone()]);
+                        if step.is_some() {
+                            return step;
+                        }
+                    }
+
+                    for ((_, opposite2), is_seen) in colors2.iter().zip(seen.iter()) {
+                        if !is_seen {
+                            continue;
+                        }
+                        let trapped: Vec<Cell> = Cell::all()
+                            .filter(|x| sees_any(*x, opposite1) && sees_any(*x, opposite2))
+                            .collect();
+                        let step = coloring_step(Technique::MultiColoring, candidates, digit, trapped, vec![cluster1.clone(), cluster2.clone()]);
+                        if step.is_some() {
+                            return step;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::{SolverOptions, Technique};
+    use crate::techniques::assert_first_step_with;
+
+    // 他の手法で先に候補が減らないように、シングルとサブセットのあとに塗り分けを使う
+    fn options(technique: Technique) -> SolverOptions {
+        SolverOptions::singles_and_subsets().with(technique)
+    }
+
+    // 両方の色から見えるマス
+    #[test]
+    fn simple_coloring_trap() {
+        assert_first_step_with(
+            "..8...4.....26..89...........35..9.7.1.3.....5....2..46.78......349..7..2....73..",
+            Technique::SimpleColoring,
+            &options(Technique::SimpleColoring),
+            "Simple Coloring: r3c8<>2 [colors r3c3, r4c2 | r4c8, r5c3]",
+        );
+    }
+
+    // 同じ色どうしが見えているので、その色がすべて偽
+    #[test]
+    fn simple_coloring_wrap() {
+        assert_first_step_with(
+            "...7..5...8..9.1..5..2...47...5.93......8...1..6....2....4.7..6.9...6...7...2....",
+            Technique::SimpleColoring,
+            &options(Technique::SimpleColoring),
+            "Simple Coloring: r2c8<>6, r3c5<>6, r4c8<>6, r5c4<>6, r5c7<>6 [colors r2c4, r3c7, r4c5 | r2c8, r3c5, r4c8, r5c4, r5c7]",
+        );
+    }
+
+    // 2つの塗り分けの反対色の両方から見えるマス
+    #[test]
+    fn multi_coloring_trap() {
+        assert_first_step_with(
+            ".6.1.5.2......631..4..2.5....78...61.8........1..6.7.....24...5..2..769.85.......",
+            Technique::MultiColoring,
+            &options(Technique::MultiColoring),
+            "Multi-Coloring: r1c5<>8, r3c8<>8 [colors r1c7 | r7c7; r3c6, r8c5 | r7c6, r8c9]",
+        );
+    }
+
+    // 相手の塗り分けの両方の色が見えている色は偽
+    #[test]
+    fn multi_coloring_wrap() {
+        assert_first_step_with(
+            "3.1.........3...5..8...4....1924.7...6.....4...2.....6...48...2..7.1.3....6..21..",
+            Technique::MultiColoring,
+            &options(Technique::MultiColoring),
+            "Multi-Coloring: r2c9<>1, r3c4<>1, r6c6<>1 [colors r2c6 | r2c9, r3c4, r6c6; r3c8, r5c9 | r5c4, r6c8]",
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use crate::candidate_grid::CandidateGrid;
+use crate::grid::{Cell, Digit, Unit, SIZE};
+use crate::solver::{SolveStep, StepDetail, Technique};
+
+use super::{combinations, elimination_step};
+
+// 行番号(列番号)からユニットを作る
+type LineUnit = fn(usize) -> Unit;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum FishShape {
+    Basic,
+    // coverからはみ出た候補 (ヒレ) が1つのブロックにある
+    Finned,
+    // ヒレを除くとbaseのユニットに候補が1つしか残らないものがある
+    Sashimi,
+}
+
+// 数字ごとに、size本の行(列)の候補がsize本の列(行)に収まっていれば、
+// その列(行)の他のマスから数字を除外する。ヒレがあればヒレと同じブロックのマスに限る
+pub(super) fn find_fish(candidates: &CandidateGrid, size: usize, shape: FishShape, technique: Technique) -> Option<SolveStep> {
+    let orientations: [(LineUnit, LineUnit); 2] = [
+        (Unit::Row, Unit::Col),
+        (Unit::Col, Unit::Row),
+    ];
+
+    for digit in Digit::all() {
+        for (base_unit, cover_unit) in orientations.iter() {
+            let lines: Vec<usize> = (0..SIZE)
+                .filter(|x| !candidates.is_placed_in(base_unit(*x), digit))
+                .filter(|x| candidates.positions(base_unit(*x), digit) != 0)
+                .collect();
+
+            for base_lines in combinations(&lines, size) {
+                let positions = base_lines.iter()
+                    .fold(0, |acc, x| acc | candidates.positions(base_unit(*x), digit));
+                let position_count = positions.count_ones() as usize;
+                let cover_lines_list = match shape {
+                    FishShape::Basic if position_count == size => vec![position_indexes(positions)],
+                    FishShape::Finned | FishShape::Sashimi if position_count > size && position_count <= size + 3 => {
+                        combinations(&position_indexes(positions), size)
+                    }
+                    _ => continue,
+                };
+
+                let base: Vec<Unit> = base_lines.iter().map(|x| base_unit(*x)).collect();
+                for cover_lines in cover_lines_list {
+                    let cover: Vec<Unit> = cover_lines.iter().map(|x| cover_unit(*x)).collect();
+                    let step = fish_step(candidates, technique, shape, digit, &base, &cover);
+                    if step.is_some() {
+                        return step;
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn position_indexes(positions: u16) -> Vec<usize> {
+    (0..SIZE).filter(|x| positions & (1 << x) != 0).collect()
+}
+
+fn fish_step(
+    candidates: &CandidateGrid,
+    technique: Technique,
+    shape: FishShape,
+    digit: Digit,
+    base: &[Unit],
+    cover: &[Unit],
+) -> Option<SolveStep> {
+    let cover_counts: Vec<usize> = base.iter()
+        .map(|unit| {
+            candidates.cells_with(*unit, digit).iter()
+                .filter(|x| cover.iter().any(|y| y.contains(**x)))
+                .count()
+        })
+        .collect();
+    if cover_counts.contains(&0) {
+        return None;
+    }
+
+    let cells: Vec<Cell> = base.iter()
+        .flat_map(|x| candidates.cells_with(*x, digit))
+        .collect();
+    let fins: Vec<Cell> = cells.iter()
+        .filter(|x| !cover.iter().any(|y| y.contains(**x)))
+        .copied()
+        .collect();
+    let is_sashimi = cover_counts.contains(&1);
+    match shape {
+        FishShape::Basic if fins.is_empty() => {}
+        FishShape::Finned if !fins.is_empty() && !is_sashimi => {}
+        FishShape::Sashimi if !fins.is_empty() && is_sashimi => {}
+        _ => return None,
+    }
+
+    let fin_block = match fins.first() {
+        Some(fin) => {
+            let block = Unit::Block(fin.block_index());
+            if !fins.iter().all(|x| block.contains(*x)) {
+                return None;
+            }
+            Some(block)
+        }
+        None => None,
+    };
+
+    let eliminations = cover.iter()
+        .flat_map(|x| x.cells())
+        .filter(|x| !base.iter().any(|y| y.contains(*x)))
+        .filter(|x| fin_block.is_none_or(|y| y.contains(*x)))
+        .map(|x| (x, digit));
+    let units: Vec<Unit> = base.iter().chain(cover.iter()).copied().collect();
+    elimination_step(technique, candidates, eliminations, units, cells)
+        .map(|step| SolveStep {
+            detail: StepDetail::Fish {
+                digit,
+                base: base.to_vec(),
+                cover: cover.to_vec(),
+                fins,
+            },
+            ..step
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Technique;
+    use crate::techniques::{after_intersections, assert_first_step, assert_first_step_with};
+
+    #[test]
+    fn x_wing() {
+        assert_first_step(
+            "..6...1..1...5..7...7...95.6...42..1......8..4...175.......82...893.....5....9..3",
+            Technique::XWing,
+            "X-Wing: r1c4<>9, r5c2<>9, r5c4<>9, r5c8<>9, r5c9<>9 [base col 1, col 5; cover row 1, row 5]",
+        );
+    }
+
+    #[test]
+    fn swordfish() {
+        assert_first_step(
+            "..6..5..1......3.81.9.4.6..5...2..3...14.......47....66...1.7.....8.9..4.4.....8.",
+            Technique::Swordfish,
+            "Swordfish: r1c2<>8, r5c6<>8, r6c6<>8 [base row 3, row 4, row 7; cover col 2, col 3, col 6]",
+        );
+    }
+
+    #[test]
+    fn jellyfish() {
+        assert_first_step(
+            ".2.....3.....6....1.3.....5.5...7......2....92.7.9...1.81.4..7..3....1..9....2..6",
+            Technique::Jellyfish,
+            "Jellyfish: r2c4<>8, r2c6<>8, r2c7<>8, r4c4<>8, r4c7<>8, r5c8<>8 [base row 3, row 6, row 8, row 9; cover col 4, col 6, col 7, col 8]",
+        );
+    }
+
+    #[test]
+    fn finned_x_wing() {
+        assert_first_step(
+            "..52......3...1.......7.49.8....7...2.43..15.....1.8....61...7.59...6.4.....42...",
+            Technique::FinnedXWing,
+            "Finned X-Wing: r1c2<>1 [base col 1, col 8; cover row 1, row 9; fins r3c1]",
+        );
+    }
+
+    #[test]
+    fn finned_swordfish() {
+        assert_first_step(
+            ".74.....69...8.....5...31.7..3..6.5....81..6..6.5....9....9..4..4216...5......2..",
+            Technique::FinnedSwordfish,
+            "Finned Swordfish: r2c6<>2 [base row 1, row 3, row 6; cover col 1, col 6, col 8; fins r1c4, r1c5, r3c4, r3c5]",
+        );
+    }
+
+    #[test]
+    fn finned_jellyfish() {
+        assert_first_step_with(
+            ".9...62..3..5...69......58.9.....3.....2.....86..1.......7.......7..1.34.1..84..7",
+            Technique::FinnedJellyfish,
+            &after_intersections(Technique::FinnedJellyfish),
+            "Finned Jellyfish: r5c7<>1 [base col 1, col 4, col 8, col 9; cover row 1, row 3, row 5, row 7; fins r4c8, r4c9]",
+        );
+    }
+
+    #[test]
+    fn sashimi_x_wing() {
+        assert_first_step(
+            ".........29.....7338..4.69...8....3..6.....574....7.2...9.8.2......64...85.2.....",
+            Technique::SashimiXWing,
+            "Sashimi X-Wing: r8c3<>7 [base row 3, row 7; cover col 3, col 4; fins r7c1, r7c2]",
+        );
+    }
+
+    #[test]
+    fn sashimi_swordfish() {
+        assert_first_step(
+            ".74.....69...8.....5...31.7..3..6.5....81..6..6.5....9....9..4..4216...5......2..",
+            Technique::SashimiSwordfish,
+            "Sashimi Swordfish: r2c4<>2 [base col 2, col 5, col 9; cover row 2, row 4, row 5; fins r1c5, r3c5]",
+        );
+    }
+
+    #[test]
+    fn sashimi_jellyfish() {
+        assert_first_step_with(
+            ".....1..9...6...8.7.9.8.4..49......6..7..2..43...6......3...5...2.35...15.4.17.9.",
+            Technique::SashimiJellyfish,
+            &after_intersections(Technique::SashimiJellyfish),
+            "Sashimi Jellyfish: r8c8<>6 [base row 3, row 5, row 7, row 9; cover col 1, col 2, col 6, col 8; fins r9c7]",
+        );
+    }
+}
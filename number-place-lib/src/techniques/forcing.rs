@@ -0,0 +1,326 @@
+use crate::candidate_grid::CandidateGrid;
+use crate::grid::{Cell, Digit, Unit, CELL_COUNT, SIZE};
+use crate::solver::{Candidate, SolveStep, StepAction, StepDetail, Technique};
+
+// 分岐ごとの配置の列と、その分岐が矛盾したか
+type ForcingPath = (Vec<Candidate>, bool);
+
+// 仮定から導いた配置。depthは何巡目のシングルで導いたか (仮定は0)、
+// reasonsはこの配置の根拠になった配置の番号
+struct Derivation {
+    candidate: Candidate,
+    depth: usize,
+    reasons: Vec<usize>,
+}
+
+// 1つの候補を仮に置き、シングルだけを最大max_depth巡まで適用した結果。
+// single_digitなら仮定した数字のヒドゥンシングルだけを使う。
+// 除外された候補ごとに、それを除外した配置の番号を覚えておき、矛盾や結論までの経路を辿れるようにする
+struct Branch {
+    candidates: CandidateGrid,
+    derivations: Vec<Derivation>,
+    causes: [[Option<usize>; SIZE + 1]; CELL_COUNT],
+    depth: usize,
+    contradiction: Option<Vec<usize>>,
+}
+
+impl Branch {
+    fn new(root: &CandidateGrid, assumption: Candidate, max_depth: usize, single_digit: bool) -> Branch {
+        let mut branch = Branch {
+            candidates: root.clone(),
+            derivations: Vec::new(),
+            causes: [[None; SIZE + 1]; CELL_COUNT],
+            depth: 0,
+            contradiction: None,
+        };
+        branch.place(assumption, Vec::new());
+
+        loop {
+            branch.contradiction = branch.find_contradiction(root);
+            if branch.contradiction.is_some() || branch.depth >= max_depth {
+                break;
+            }
+            let mut singles = branch.singles(root);
+            if single_digit {
+                singles.retain(|(x, _)| x.digit == assumption.digit);
+            }
+            if singles.is_empty() {
+                break;
+            }
+            branch.depth += 1;
+            for (candidate, reasons) in singles {
+                if branch.candidates.is_empty_cell(candidate.cell) && branch.candidates.has(candidate.cell, candidate.digit) {
+                    branch.place(candidate, reasons);
+                }
+            }
+        }
+
+        branch
+    }
+
+    fn place(&mut self, candidate: Candidate, reasons: Vec<usize>) {
+        let index = self.derivations.len();
+        let (cell, digit) = (candidate.cell, candidate.digit);
+        for other in self.candidates.digits(cell).filter(|x| *x != digit) {
+            self.causes[cell.index()][other.get() as usize].get_or_insert(index);
+        }
+        let peers: Vec<Cell> = cell.units().iter()
+            .flat_map(|x| x.cells())
+            .filter(|x| *x != cell && self.candidates.has(*x, digit))
+            .collect();
+        for other in peers {
+            self.causes[other.index()][digit.get() as usize].get_or_insert(index);
+        }
+
+        self.candidates.place(cell, digit);
+        self.derivations.push(Derivation { candidate, depth: self.depth, reasons });
+    }
+
+    // 仮定する前にあった候補のうち、除外されたものの原因
+    fn reasons<'a>(&'a self, root: &'a CandidateGrid, removed: impl Iterator<Item = (Cell, Digit)> + 'a) -> Vec<usize> {
+        removed
+            .filter(|(cell, digit)| root.has(*cell, *digit))
+            .filter_map(|(cell, digit)| self.causes[cell.index()][digit.get() as usize])
+            .collect()
+    }
+
+    // 次の巡で置けるネイキッドシングルとヒドゥンシングル
+    fn singles(&self, root: &CandidateGrid) -> Vec<(Candidate, Vec<usize>)> {
+        let mut singles: Vec<(Candidate, Vec<usize>)> = Vec::new();
+        for cell in Cell::all().filter(|x| self.candidates.is_empty_cell(*x) && self.candidates.count(*x) == 1) {
+            let digit = self.candidates.digits(cell).next().unwrap();
+            let removed = root.digits(cell).filter(move |x| *x != digit).map(move |x| (cell, x));
+            singles.push((Candidate { cell, digit }, self.reasons(root, removed)));
+        }
+        for unit in Unit::all() {
+            for digit in Digit::all() {
+                if self.candidates.is_placed_in(unit, digit) || self.candidates.positions(unit, digit).count_ones() != 1 {
+                    continue;
+                }
+                let cell = self.candidates.cells_with(unit, digit)[0];
+                if singles.iter().any(|(x, _)| x.cell == cell) {
+                    continue;
+                }
+                let removed = unit.cells().filter(move |x| *x != cell).map(move |x| (x, digit));
+                singles.push((Candidate { cell, digit }, self.reasons(root, removed)));
+            }
+        }
+        singles
+    }
+
+    // 候補の無くなったマスか、数字を入れる場所の無くなったユニット
+    fn find_contradiction(&self, root: &CandidateGrid) -> Option<Vec<usize>> {
+        if let Some(cell) = Cell::all().find(|x| self.candidates.is_empty_cell(*x) && self.candidates.mask(*x) == 0) {
+            return Some(self.reasons(root, root.digits(cell).map(move |x| (cell, x))));
+        }
+        for unit in Unit::all() {
+            if let Some(digit) = Digit::all().find(|x| !self.candidates.is_placed_in(unit, *x) && self.candidates.positions(unit, *x) == 0) {
+                return Some(self.reasons(root, unit.cells().map(move |x| (x, digit))));
+            }
+        }
+        None
+    }
+
+    fn is_placed(&self, candidate: Candidate) -> bool {
+        self.candidates.grid().get(candidate.cell.row_index, candidate.cell.col_index) == Some(candidate.digit)
+    }
+
+    fn is_eliminated(&self, candidate: Candidate) -> bool {
+        !self.is_placed(candidate) && !self.candidates.has(candidate.cell, candidate.digit)
+    }
+
+    // reasonsに至るまでの配置を導いた順に並べる
+    fn path(&self, reasons: &[usize]) -> Vec<Candidate> {
+        let mut used = vec![false; self.derivations.len()];
+        let mut stack: Vec<usize> = reasons.to_vec();
+        while let Some(index) = stack.pop() {
+            if !used[index] {
+                used[index] = true;
+                stack.extend_from_slice(&self.derivations[index].reasons);
+            }
+        }
+        self.derivations.iter()
+            .zip(used.iter())
+            .filter(|(_, x)| **x)
+            .map(|(x, _)| x.candidate)
+            .collect()
+    }
+}
+
+fn empty_candidates(candidates: &CandidateGrid) -> impl Iterator<Item = Candidate> + '_ {
+    Cell::all()
+        .filter(move |x| candidates.is_empty_cell(*x))
+        .flat_map(move |x| candidates.digits(x).map(move |digit| Candidate { cell: x, digit }))
+}
+
+fn forcing_step(technique: Technique, action: StepAction, units: Vec<Unit>, depth: usize, branches: Vec<ForcingPath>) -> SolveStep {
+    let mut cells: Vec<Cell> = branches.iter().flat_map(|x| x.0.iter()).map(|x| x.cell).collect();
+    cells.sort();
+    cells.dedup();
+    SolveStep {
+        technique,
+        action,
+        units,
+        cells,
+        detail: StepDetail::Forcing { depth, branches },
+    }
+}
+
+// 候補を仮に置き、同じ数字だけで辿って矛盾すれば、その候補は偽。最も浅い巡で矛盾するものを採用する
+pub(super) fn find_nishio(candidates: &CandidateGrid, max_depth: usize) -> Option<SolveStep> {
+    let mut best: Option<(Candidate, Branch)> = None;
+    for candidate in empty_candidates(candidates) {
+        if best.as_ref().is_some_and(|x| x.1.depth == 0) {
+            break;
+        }
+        let limit = best.as_ref().map_or(max_depth, |x| x.1.depth - 1);
+        let branch = Branch::new(candidates, candidate, limit, true);
+        if branch.contradiction.is_some() {
+            best = Some((candidate, branch));
+        }
+    }
+
+    best.map(|(candidate, branch)| {
+        let path = branch.path(branch.contradiction.as_ref().unwrap());
+        forcing_step(Technique::Nishio, StepAction::Eliminate(vec![candidate]), Vec::new(), branch.depth, vec![(path, true)])
+    })
+}
+
+// 矛盾しなかった分岐のすべてで共通して置かれる候補、または共通して除外される候補。
+// 矛盾した分岐は偽なので結論に影響しない。最も浅い巡で導けるものを採用する
+fn common_conclusion(candidates: &CandidateGrid, branches: &[Branch]) -> Option<(StepAction, usize, Vec<ForcingPath>)> {
+    let (failed, alive): (Vec<&Branch>, Vec<&Branch>) = branches.iter().partition(|x| x.contradiction.is_some());
+    if alive.is_empty() {
+        return None;
+    }
+    let failed_depth = failed.iter().map(|x| x.depth).max().unwrap_or(0);
+    let failed_paths: Vec<ForcingPath> = failed.iter()
+        .map(|x| (x.path(x.contradiction.as_ref().unwrap()), true))
+        .collect();
+
+    let mut best: Option<(StepAction, usize, Vec<ForcingPath>)> = None;
+    let mut consider = |action: StepAction, indices: Vec<usize>| {
+        let depth = alive.iter().zip(indices.iter())
+            .map(|(x, i)| x.derivations[*i].depth)
+            .fold(failed_depth, usize::max);
+        if best.as_ref().is_none_or(|x| depth < x.1) {
+            let mut paths: Vec<ForcingPath> = alive.iter().zip(indices.iter())
+                .map(|(x, i)| (x.path(&[*i]), false))
+                .collect();
+            paths.extend(failed_paths.iter().cloned());
+            best = Some((action, depth, paths));
+        }
+    };
+
+    for derivation in alive[0].derivations.iter() {
+        let candidate = derivation.candidate;
+        if !candidates.is_empty_cell(candidate.cell) || !alive.iter().all(|x| x.is_placed(candidate)) {
+            continue;
+        }
+        let indices = alive.iter()
+            .map(|x| x.derivations.iter().position(|y| y.candidate == candidate).unwrap())
+            .collect();
+        consider(StepAction::Place(candidate.cell, candidate.digit), indices);
+    }
+
+    for candidate in empty_candidates(candidates) {
+        if !alive.iter().all(|x| x.is_eliminated(candidate)) {
+            continue;
+        }
+        let indices = alive.iter()
+            .map(|x| x.causes[candidate.cell.index()][candidate.digit.get() as usize].unwrap())
+            .collect();
+        consider(StepAction::Eliminate(vec![candidate]), indices);
+    }
+
+    best
+}
+
+// マスに入りうる数字をそれぞれ仮定し、どの場合にも同じ結論になればその結論は真
+pub(super) fn find_cell_forcing_chain(candidates: &CandidateGrid, max_depth: usize) -> Option<SolveStep> {
+    let mut best: Option<SolveStep> = None;
+    let mut best_depth = usize::MAX;
+    for cell in Cell::all().filter(|x| candidates.count(*x) >= 2) {
+        let branches: Vec<Branch> = candidates.digits(cell)
+            .map(|x| Branch::new(candidates, Candidate { cell, digit: x }, max_depth, false))
+            .collect();
+        if let Some((action, depth, paths)) = common_conclusion(candidates, &branches) {
+            if depth < best_depth {
+                best_depth = depth;
+                best = Some(forcing_step(Technique::CellForcingChain, action, Vec::new(), depth, paths));
+            }
+        }
+    }
+
+    best
+}
+
+// ユニット内で数字が入りうるマスをそれぞれ仮定し、どの場合にも同じ結論になればその結論は真
+pub(super) fn find_digit_forcing_chain(candidates: &CandidateGrid, max_depth: usize) -> Option<SolveStep> {
+    let mut best: Option<SolveStep> = None;
+    let mut best_depth = usize::MAX;
+    for unit in Unit::all() {
+        for digit in Digit::all() {
+            if candidates.positions(unit, digit).count_ones() < 2 {
+                continue;
+            }
+            let branches: Vec<Branch> = candidates.cells_with(unit, digit).into_iter()
+                .map(|x| Branch::new(candidates, Candidate { cell: x, digit }, max_depth, false))
+                .collect();
+            if let Some((action, depth, paths)) = common_conclusion(candidates, &branches) {
+                if depth < best_depth {
+                    best_depth = depth;
+                    best = Some(forcing_step(Technique::DigitForcingChain, action, vec![unit], depth, paths));
+                }
+            }
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::SolverOptions;
+    use crate::techniques::{assert_first_step_with, find_step, first_step};
+
+    const PROBLEM: &str = ".....1..9...6...8.7.9.8.4..49......6..7..2..43...6......3...5...2.35...15.4.17.9.";
+
+    // 他の手法で先に解けないように、シングルとサブセットのあとに仮定を使う
+    fn options(technique: Technique) -> SolverOptions {
+        SolverOptions::singles_and_subsets().with(technique)
+    }
+
+    #[test]
+    fn nishio() {
+        let technique = Technique::Nishio;
+        assert_first_step_with(PROBLEM, technique, &options(technique), "Nishio: r1c7<>6 [6r1c7 -> 6r3c2 -> contradiction; depth 1]");
+    }
+
+    #[test]
+    fn cell_forcing_chain() {
+        let technique = Technique::CellForcingChain;
+        assert_first_step_with(PROBLEM, technique, &options(technique), "Cell Forcing Chain: r7c9<>2 [2r3c4 -> 2r7c5 | 5r3c4 -> 2r3c9; depth 1]");
+    }
+
+    #[test]
+    fn digit_forcing_chain() {
+        let technique = Technique::DigitForcingChain;
+        let (_, step) = first_step(PROBLEM, technique, &options(technique));
+        assert_eq!(step.to_string(), "Digit Forcing Chain: r1c7<>6 [6r8c3 -> 6r9c7 | 6r9c2 -> 6r3c8; depth 1]");
+        assert_eq!(step.units, vec![Unit::Block(6)]);
+    }
+
+    // 1巡のシングルで導ける結論は、0巡までに制限すると見つからない
+    #[test]
+    fn max_forcing_depth_limits_search() {
+        for technique in [Technique::Nishio, Technique::CellForcingChain, Technique::DigitForcingChain] {
+            let (candidates, _) = first_step(PROBLEM, technique, &options(technique));
+            let options = SolverOptions::default();
+            assert_eq!(find_step(technique, &candidates, &options.clone().with_max_forcing_depth(0)), None);
+            let step = find_step(technique, &candidates, &options.with_max_forcing_depth(1)).unwrap();
+            assert!(matches!(step.detail, StepDetail::Forcing { depth: 1, .. }), "{}", step);
+        }
+    }
+}
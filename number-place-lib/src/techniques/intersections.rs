@@ -0,0 +1,109 @@
+use crate::candidate_grid::CandidateGrid;
+use crate::grid::{Digit, Unit, SIZE};
+use crate::solver::{SolveStep, Technique};
+
+use super::elimination_step;
+
+// いずれにしても理論: ブロック内で数字の入る候補が1つの行(列)に収まっていれば、
+// その行(列)のブロック外のマスからその数字を除外する
+pub(super) fn find_pointing(candidates: &CandidateGrid) -> Option<SolveStep> {
+    for block_index in 0..SIZE {
+        let block = Unit::Block(block_index);
+        for digit in Digit::all() {
+            let cells = candidates.cells_with(block, digit);
+            if cells.len() < 2 {
+                continue;
+            }
+
+            let lines = [
+                Unit::Row(cells[0].row_index),
+                Unit::Col(cells[0].col_index),
+            ];
+            for line in lines.iter() {
+                if !cells.iter().all(|x| line.contains(*x)) {
+                    continue;
+                }
+                let eliminations = line.cells()
+                    .filter(|x| !block.contains(*x))
+                    .map(|x| (x, digit));
+                let step = elimination_step(Technique::Pointing, candidates, eliminations, vec![block, *line], cells.clone());
+                if step.is_some() {
+                    return step;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// ボックス/ライン削減: 行(列)で数字の入る候補が1つのブロックに収まっていれば、
+// そのブロックの行(列)外のマスからその数字を除外する
+pub(super) fn find_box_line_reduction(candidates: &CandidateGrid) -> Option<SolveStep> {
+    let lines = (0..SIZE).map(Unit::Row).chain((0..SIZE).map(Unit::Col));
+    for line in lines {
+        for digit in Digit::all() {
+            let cells = candidates.cells_with(line, digit);
+            if cells.len() < 2 {
+                continue;
+            }
+
+            let block = Unit::Block(cells[0].block_index());
+            if !cells.iter().all(|x| block.contains(*x)) {
+                continue;
+            }
+            let eliminations = block.cells()
+                .filter(|x| !line.contains(*x))
+                .map(|x| (x, digit));
+            let step = elimination_step(Technique::BoxLineReduction, candidates, eliminations, vec![line, block], cells);
+            if step.is_some() {
+                return step;
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Technique;
+    use crate::techniques::assert_first_step;
+
+    #[test]
+    fn pointing_in_col() {
+        assert_first_step(
+            ".........29.....7338..4.69...8....3..6.....574....7.2...9.8.2......64...85.2.....",
+            Technique::Pointing,
+            "Pointing: r7c9<>6, r9c9<>6 [block 6, col 9]",
+        );
+    }
+
+    #[test]
+    fn pointing_in_row() {
+        assert_first_step(
+            ".....2.....5..1.6.6.8...2.........76...85...4..9.4.8..9.4..7......9...311...6...9",
+            Technique::Pointing,
+            "Pointing: r3c8<>5, r3c9<>5 [block 2, row 3]",
+        );
+    }
+
+    // 行(列)で1つのブロックに収まる数字は、ポインティングとは別の手法として記録する
+    #[test]
+    fn box_line_reduction_in_row() {
+        assert_first_step(
+            "......93......61.7.8.9..54............84657.22...1..6..1..54...7.4......8.6.3.2..",
+            Technique::BoxLineReduction,
+            "Box/Line Reduction: r4c1<>3, r4c2<>3, r4c3<>3, r6c2<>3, r6c3<>3 [row 5, block 4]",
+        );
+    }
+
+    #[test]
+    fn box_line_reduction_in_col() {
+        assert_first_step(
+            "4......3..2..8......1..76......1...5....9..84.923......1...2..8...53..7.75...9...",
+            Technique::BoxLineReduction,
+            "Box/Line Reduction: r3c8<>2 [col 9, block 3]",
+        );
+    }
+}
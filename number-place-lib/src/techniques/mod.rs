@@ -0,0 +1,173 @@
+use crate::candidate_grid::CandidateGrid;
+use crate::solver::{Candidate, SolveStep, SolverOptions, StepAction, StepDetail, Technique};
+use crate::grid::{Cell, Digit, Unit};
+
+mod singles;
+mod intersections;
+mod subsets;
+mod fish;
+mod wings;
+mod single_digit;
+mod coloring;
+mod chains;
+mod uniqueness;
+mod als;
+mod forcing;
+
+use chains::ChainKind;
+use fish::FishShape;
+
+pub(crate) fn find_step(technique: Technique, candidates: &CandidateGrid, options: &SolverOptions) -> Option<SolveStep> {
+    match technique {
+        Technique::HiddenSingle => singles::find_hidden_single(candidates),
+        Technique::NakedSingle => singles::find_naked_single(candidates),
+        Technique::Pointing => intersections::find_pointing(candidates),
+        Technique::BoxLineReduction => intersections::find_box_line_reduction(candidates),
+        Technique::NakedPair => subsets::find_naked_subset(candidates, 2, technique),
+        Technique::NakedTriple => subsets::find_naked_subset(candidates, 3, technique),
+        Technique::NakedQuad => subsets::find_naked_subset(candidates, 4, technique),
+        Technique::HiddenPair => subsets::find_hidden_subset(candidates, 2, technique),
+        Technique::HiddenTriple => subsets::find_hidden_subset(candidates, 3, technique),
+        Technique::HiddenQuad => subsets::find_hidden_subset(candidates, 4, technique),
+        Technique::XWing => fish::find_fish(candidates, 2, FishShape::Basic, technique),
+        Technique::Swordfish => fish::find_fish(candidates, 3, FishShape::Basic, technique),
+        Technique::Jellyfish => fish::find_fish(candidates, 4, FishShape::Basic, technique),
+        Technique::FinnedXWing => fish::find_fish(candidates, 2, FishShape::Finned, technique),
+        Technique::FinnedSwordfish => fish::find_fish(candidates, 3, FishShape::Finned, technique),
+        Technique::FinnedJellyfish => fish::find_fish(candidates, 4, FishShape::Finned, technique),
+        Technique::SashimiXWing => fish::find_fish(candidates, 2, FishShape::Sashimi, technique),
+        Technique::SashimiSwordfish => fish::find_fish(candidates, 3, FishShape::Sashimi, technique),
+        Technique::SashimiJellyfish => fish::find_fish(candidates, 4, FishShape::Sashimi, technique),
+        Technique::Skyscraper => single_digit::find_skyscraper(candidates),
+        Technique::TwoStringKite => single_digit::find_two_string_kite(candidates),
+        Technique::EmptyRectangle => single_digit::find_empty_rectangle(candidates),
+        Technique::XYWing => wings::find_xy_wing(candidates),
+        Technique::XYZWing => wings::find_xyz_wing(candidates),
+        Technique::WWing => wings::find_w_wing(candidates),
+        Technique::UniqueRectangleType1 => uniqueness::find_unique_rectangle(candidates, 1, technique),
+        Technique::UniqueRectangleType2 => uniqueness::find_unique_rectangle(candidates, 2, technique),
+        Technique::UniqueRectangleType3 => uniqueness::find_unique_rectangle(candidates, 3, technique),
+        Technique::UniqueRectangleType4 => uniqueness::find_unique_rectangle(candidates, 4, technique),
+        Technique::UniqueRectangleType5 => uniqueness::find_unique_rectangle(candidates, 5, technique),
+        Technique::UniqueRectangleType6 => uniqueness::find_unique_rectangle(candidates, 6, technique),
+        Technique::AvoidableRectangle => uniqueness::find_avoidable_rectangle(candidates),
+        Technique::BugPlusOne => uniqueness::find_bug_plus_one(candidates),
+        Technique::SimpleColoring => coloring::find_simple_coloring(candidates),
+        Technique::MultiColoring => coloring::find_multi_coloring(candidates),
+        Technique::XChain => chains::find_chain(candidates, ChainKind::X, options.max_chain_length),
+        Technique::XYChain => chains::find_chain(candidates, ChainKind::XY, options.max_chain_length),
+        Technique::AlsXz => als::find_als_xz(candidates),
+        Technique::AlsXyWing => als::find_als_xy_wing(candidates),
+        Technique::SueDeCoq => als::find_sue_de_coq(candidates),
+        Technique::AIC => chains::find_chain(candidates, ChainKind::Alternating, options.max_chain_length),
+        Technique::Nishio => forcing::find_nishio(candidates, options.max_forcing_depth),
+        Technique::CellForcingChain => forcing::find_cell_forcing_chain(candidates, options.max_forcing_depth),
+        Technique::DigitForcingChain => forcing::find_digit_forcing_chain(candidates, options.max_forcing_depth),
+    }
+}
+
+// itemsからsize個を選ぶ組み合わせ。並びは元の順序を保つ
+fn combinations<T: Copy>(items: &[T], size: usize) -> Vec<Vec<T>> {
+    if size == 0 {
+        return vec![Vec::new()];
+    }
+
+    let mut result: Vec<Vec<T>> = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        for mut rest in combinations(&items[i + 1..], size - 1) {
+            rest.insert(0, *item);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+// 候補が残っているものだけを除外対象にする。除外するものがなければNone
+fn elimination_step(
+    technique: Technique,
+    candidates: &CandidateGrid,
+    eliminations: impl Iterator<Item = (Cell, Digit)>,
+    units: Vec<Unit>,
+    cells: Vec<Cell>,
+) -> Option<SolveStep> {
+    let mut eliminations: Vec<Candidate> = eliminations
+        .filter(|(cell, digit)| candidates.has(*cell, *digit))
+        .map(|(cell, digit)| Candidate { cell, digit })
+        .collect();
+    eliminations.sort();
+    eliminations.dedup();
+
+    if eliminations.is_empty() {
+        return None;
+    }
+
+    Some(SolveStep {
+        technique,
+        action: StepAction::Eliminate(eliminations),
+        units,
+        cells,
+        detail: StepDetail::None,
+    })
+}
+
+// 手法のテスト用。optionsの手法で解き進め、techniqueが初めて使われた盤面とその手を返す。
+// 途中の手も含めて解と食い違わないことを確かめる
+#[cfg(test)]
+fn first_step(problem: &str, technique: Technique, options: &SolverOptions) -> (CandidateGrid, SolveStep) {
+    use crate::backtrack::{solve_exact, SolveResult};
+    use crate::grid::Grid;
+
+    let grid: Grid = problem.parse().unwrap();
+    let solution = match solve_exact(&grid) {
+        SolveResult::Solved(solution) => solution,
+        result => panic!("not a proper puzzle: {:?}", result),
+    };
+
+    let mut candidates = CandidateGrid::new(&grid);
+    loop {
+        let step = Technique::all().iter()
+            .filter(|x| options.is_enabled(**x))
+            .find_map(|x| find_step(*x, &candidates, options))
+            .unwrap_or_else(|| panic!("{} is not found", technique));
+        match &step.action {
+            StepAction::Place(cell, digit) => {
+                assert_eq!(solution.get(cell.row_index, cell.col_index), Some(*digit), "{}", step);
+            }
+            StepAction::Eliminate(eliminations) => {
+                for x in eliminations {
+                    assert_ne!(solution.get(x.cell.row_index, x.cell.col_index), Some(x.digit), "{}", step);
+                }
+            }
+        }
+        if step.technique == technique {
+            return (candidates, step);
+        }
+        candidates.apply(&step);
+    }
+}
+
+// 他の手法が先に見つからないように、シングルと交差のあとにtechniqueだけを使う
+#[cfg(test)]
+fn after_intersections(technique: Technique) -> SolverOptions {
+    SolverOptions {
+        techniques: vec![
+            Technique::HiddenSingle,
+            Technique::NakedSingle,
+            Technique::Pointing,
+            Technique::BoxLineReduction,
+            technique,
+        ],
+        ..SolverOptions::default()
+    }
+}
+
+#[cfg(test)]
+fn assert_first_step(problem: &str, technique: Technique, expected: &str) {
+    assert_first_step_with(problem, technique, &SolverOptions::default(), expected);
+}
+
+#[cfg(test)]
+fn assert_first_step_with(problem: &str, technique: Technique, options: &SolverOptions, expected: &str) {
+    let (_, step) = first_step(problem, technique, options);
+    assert_eq!(step.to_string(), expected);
+}
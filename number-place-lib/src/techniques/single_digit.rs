@@ -0,0 +1,203 @@
+use crate::candidate_grid::CandidateGrid;
+use crate::grid::{Cell, Digit, Unit, SIZE};
+use crate::solver::{Candidate, Link, SolveStep, StepDetail, Technique};
+
+use super::elimination_step;
+
+fn candidate(cell: Cell, digit: Digit) -> Candidate {
+    Candidate { cell, digit }
+}
+
+// 強リンクの両端を入れ替えた2通り
+fn both_ends(link: &(Unit, Cell, Cell)) -> [(Cell, Cell); 2] {
+    [(link.1, link.2), (link.2, link.1)]
+}
+
+// tipsの両方から見えるマスからdigitを除外する
+fn link_step(
+    technique: Technique,
+    candidates: &CandidateGrid,
+    digit: Digit,
+    tips: [Cell; 2],
+    units: Vec<Unit>,
+    links: Vec<Link>,
+) -> Option<SolveStep> {
+    let eliminations = Cell::all()
+        .filter(|x| x.sees(tips[0]) && x.sees(tips[1]))
+        .map(|x| (x, digit));
+    let mut cells: Vec<Cell> = links.iter()
+        .flat_map(|x| vec![x.from.cell, x.to.cell])
+        .collect();
+    cells.dedup();
+    elimination_step(technique, candidates, eliminations, units, cells)
+        .map(|step| SolveStep {
+            detail: StepDetail::Links { links },
+            ..step
+        })
+}
+
+// 平行な2本の行(列)の強リンクの片端どうしが同じ列(行)にあれば、
+// もう一方の端のどちらかは必ずdigitになる
+pub(super) fn find_skyscraper(candidates: &CandidateGrid) -> Option<SolveStep> {
+    for digit in Digit::all() {
+        let links = candidates.strong_links(digit);
+        let row_links: Vec<&(Unit, Cell, Cell)> = links.iter()
+            .filter(|x| matches!(x.0, Unit::Row(_)))
+            .collect();
+        let col_links: Vec<&(Unit, Cell, Cell)> = links.iter()
+            .filter(|x| matches!(x.0, Unit::Col(_)))
+            .collect();
+        let line_links = [
+            (row_links, (|x: Cell| x.col_index) as fn(Cell) -> usize),
+            (col_links, (|x: Cell| x.row_index) as fn(Cell) -> usize),
+        ];
+
+        for (lines, cross) in line_links.iter() {
+            for (i, link1) in lines.iter().enumerate() {
+                for link2 in lines.iter().skip(i + 1) {
+                    for (base1, tip1) in both_ends(link1).iter() {
+                        for (base2, tip2) in both_ends(link2).iter() {
+                            if cross(*base1) != cross(*base2) || cross(*tip1) == cross(*tip2) {
+                                continue;
+                            }
+                            let links = vec![
+                                Link::strong(candidate(*tip1, digit), candidate(*base1, digit)),
+                                Link::weak(candidate(*base1, digit), candidate(*base2, digit)),
+                                Link::strong(candidate(*base2, digit), candidate(*tip2, digit)),
+                            ];
+                            let step = link_step(Technique::Skyscraper, candidates, digit, [*tip1, *tip2], vec![link1.0, link2.0], links);
+                            if step.is_some() {
+                                return step;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// 行の強リンクと列の強リンクの片端どうしが同じブロックにあれば、
+// もう一方の端のどちらかは必ずdigitになる
+pub(super) fn find_two_string_kite(candidates: &CandidateGrid) -> Option<SolveStep> {
+    for digit in Digit::all() {
+        let links = candidates.strong_links(digit);
+        let row_links = links.iter().filter(|x| matches!(x.0, Unit::Row(_)));
+        for row_link in row_links {
+            let col_links = links.iter().filter(|x| matches!(x.0, Unit::Col(_)));
+            for col_link in col_links {
+                for (row_end, row_tip) in both_ends(row_link).iter() {
+                    for (col_end, col_tip) in both_ends(col_link).iter() {
+                        let block = Unit::Block(row_end.block_index());
+                        let is_kite = row_end != col_end
+                            && block.contains(*col_end)
+                            && !block.contains(*row_tip)
+                            && !block.contains(*col_tip);
+                        if !is_kite {
+                            continue;
+                        }
+                        let links = vec![
+                            Link::strong(candidate(*row_tip, digit), candidate(*row_end, digit)),
+                            Link::weak(candidate(*row_end, digit), candidate(*col_end, digit)),
+                            Link::strong(candidate(*col_end, digit), candidate(*col_tip, digit)),
+                        ];
+                        let step = link_step(Technique::TwoStringKite, candidates, digit, [*row_tip, *col_tip], vec![row_link.0, col_link.0, block], links);
+                        if step.is_some() {
+                            return step;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// ブロック内のdigitの候補が1つの行と1つの列の十字に収まっているとき、
+// その行(列)にかかる列(行)の強リンクのもう一方の端と十字の列(行)の交点からdigitを除外する
+pub(super) fn find_empty_rectangle(candidates: &CandidateGrid) -> Option<SolveStep> {
+    for digit in Digit::all() {
+        let links = candidates.strong_links(digit);
+        for block_index in 0..SIZE {
+            let block = Unit::Block(block_index);
+            let cells = candidates.cells_with(block, digit);
+            if cells.len() < 2 {
+                continue;
+            }
+
+            for hinge in block.cells() {
+                let (row, col) = (Unit::Row(hinge.row_index), Unit::Col(hinge.col_index));
+                let is_cross = cells.iter().all(|x| row.contains(*x) || col.contains(*x))
+                    && !cells.iter().all(|x| row.contains(*x))
+                    && !cells.iter().all(|x| col.contains(*x));
+                if !is_cross {
+                    continue;
+                }
+
+                for link in links.iter().filter(|x| !matches!(x.0, Unit::Block(_))) {
+                    for (end, tip) in both_ends(link).iter() {
+                        // 強リンクが列なら端は十字の行に、ターゲットは十字の列にある
+                        let target = match link.0 {
+                            Unit::Col(_) if row.contains(*end) => Cell::new(tip.row_index, hinge.col_index),
+                            Unit::Row(_) if col.contains(*end) => Cell::new(hinge.row_index, tip.col_index),
+                            _ => continue,
+                        };
+                        if block.contains(*end) || block.contains(*tip) || block.contains(target) {
+                            continue;
+                        }
+                        let eliminations = std::iter::once((target, digit));
+                        let links = vec![Link::strong(candidate(*tip, digit), candidate(*end, digit))];
+                        let mut step_cells = cells.clone();
+                        step_cells.extend_from_slice(&[*end, *tip]);
+                        let step = elimination_step(Technique::EmptyRectangle, candidates, eliminations, vec![block, row, col, link.0], step_cells)
+                            .map(|step| SolveStep {
+                                detail: StepDetail::Links { links },
+                                ..step
+                            });
+                        if step.is_some() {
+                            return step;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Technique;
+    use crate::techniques::assert_first_step;
+
+    #[test]
+    fn skyscraper() {
+        assert_first_step(
+            ".....1..9...6...8.7.9.8.4..49......6..7..2..43...6......3...5...2.35...15.4.17.9.",
+            Technique::Skyscraper,
+            "Skyscraper: r1c7<>6, r7c8<>6, r8c8<>6 [(6)r3c8=(6)r3c2-(6)r9c2=(6)r9c7]",
+        );
+    }
+
+    #[test]
+    fn two_string_kite() {
+        assert_first_step(
+            ".........29.....7338..4.69...8....3..6.....574....7.2...9.8.2......64...85.2.....",
+            Technique::TwoStringKite,
+            "2-String Kite: r9c3<>7 [(7)r3c3=(7)r3c4-(7)r1c5=(7)r9c5]",
+        );
+    }
+
+    #[test]
+    fn empty_rectangle() {
+        assert_first_step(
+            "..52......3...1.......7.49.8....7...2.43..15.....1.8....61...7.59...6.4.....42...",
+            Technique::EmptyRectangle,
+            "Empty Rectangle: r7c9<>5 [(5)r7c5=(5)r2c5]",
+        );
+    }
+}
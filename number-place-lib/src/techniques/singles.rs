@@ -0,0 +1,41 @@
+use crate::candidate_grid::{mask_digits, CandidateGrid};
+use crate::grid::{Cell, Digit, Unit};
+use crate::solver::{SolveStep, StepAction, StepDetail, Technique};
+
+// あるユニットで数字を入れられるマスが1つしかない
+pub(super) fn find_hidden_single(candidates: &CandidateGrid) -> Option<SolveStep> {
+    for unit in Unit::all() {
+        for digit in Digit::all() {
+            let cells = candidates.cells_with(unit, digit);
+            if cells.len() == 1 && !candidates.is_placed_in(unit, digit) {
+                return Some(SolveStep {
+                    technique: Technique::HiddenSingle,
+                    action: StepAction::Place(cells[0], digit),
+                    units: vec![unit],
+                    cells,
+                    detail: StepDetail::None,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+// あるマスに入れられる数字が1つしかない
+pub(super) fn find_naked_single(candidates: &CandidateGrid) -> Option<SolveStep> {
+    let cell = Cell::all().find(|x| candidates.count(*x) == 1)?;
+    let digit = mask_digits(candidates.mask(cell)).next()?;
+
+    Some(SolveStep {
+        technique: Technique::NakedSingle,
+        action: StepAction::Place(cell, digit),
+        units: vec![
+            Unit::Row(cell.row_index),
+            Unit::Col(cell.col_index),
+            Unit::Block(cell.block_index()),
+        ],
+        cells: vec![cell],
+        detail: StepDetail::None,
+    })
+}
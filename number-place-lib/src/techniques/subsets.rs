@@ -0,0 +1,252 @@
+use crate::candidate_grid::{mask_digits, CandidateGrid};
+use crate::grid::{Cell, Digit, Unit, SIZE};
+use crate::solver::{SolveStep, Technique};
+
+use super::{combinations, elimination_step};
+
+// ユニット内でsize個のマスの候補を合わせてsize個の数字しかなければ、
+// ユニットの他のマスからそれらの数字を除外する
+pub(super) fn find_naked_subset(candidates: &CandidateGrid, size: usize, technique: Technique) -> Option<SolveStep> {
+    for unit in Unit::all() {
+        let cells: Vec<Cell> = unit.cells()
+            .filter(|x| (2..=size as u32).contains(&candidates.count(*x)))
+            .collect();
+
+        for subset in combinations(&cells, size) {
+            let mask = subset.iter()
+                .fold(0, |acc, x| acc | candidates.mask(*x));
+            if mask.count_ones() as usize != size {
+                continue;
+            }
+
+            let eliminations = unit.cells()
+                .filter(|x| !subset.contains(x))
+                .flat_map(|x| mask_digits(mask).map(move |digit| (x, digit)));
+            let step = elimination_step(technique, candidates, eliminations, vec![unit], subset.clone());
+            if step.is_some() {
+                return step;
+            }
+        }
+    }
+
+    None
+}
+
+// ユニット内でsize個の数字の入る場所がsize個のマスに限られていれば、
+// それらのマスから他の数字を除外する
+pub(super) fn find_hidden_subset(candidates: &CandidateGrid, size: usize, technique: Technique) -> Option<SolveStep> {
+    for unit in Unit::all() {
+        let digits: Vec<Digit> = Digit::all()
+            .filter(|x| !candidates.is_placed_in(unit, *x))
+            .filter(|x| (1..=size as u32).contains(&candidates.positions(unit, *x).count_ones()))
+            .collect();
+
+        for subset in combinations(&digits, size) {
+            let positions = subset.iter()
+                .fold(0, |acc, x| acc | candidates.positions(unit, *x));
+            if positions.count_ones() as usize != size {
+                continue;
+            }
+
+            let cells: Vec<Cell> = (0..SIZE)
+                .filter(|x| positions & (1 << x) != 0)
+                .map(|x| unit.cell_at(x))
+                .collect();
+            let eliminations = cells.iter()
+                .flat_map(|x| candidates.digits(*x).map(move |digit| (*x, digit)))
+                .filter(|(_, digit)| !subset.contains(digit));
+            let step = elimination_step(technique, candidates, eliminations, vec![unit], cells.clone());
+            if step.is_some() {
+                return step;
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Technique;
+    use crate::techniques::{after_intersections, assert_first_step_with};
+
+    #[test]
+    fn naked_pair_in_row() {
+        assert_first_step_with(
+            "..8...4.....26..89...........35..9.7.1.3.....5....2..46.78......349..7..2....73..",
+            Technique::NakedPair,
+            &after_intersections(Technique::NakedPair),
+            "Naked Pair: r2c1<>1, r2c2<>5, r2c6<>1, r2c6<>5 [row 2]",
+        );
+    }
+
+    #[test]
+    fn naked_pair_in_col() {
+        assert_first_step_with(
+            "...1....4...8...6..14.5.8.....2.1.76.9....5...3.....1.....62..5.5......37.2......",
+            Technique::NakedPair,
+            &after_intersections(Technique::NakedPair),
+            "Naked Pair: r1c2<>8, r9c2<>4, r9c2<>8 [col 2]",
+        );
+    }
+
+    #[test]
+    fn naked_pair_in_block() {
+        assert_first_step_with(
+            ".9...62..3..5...69......58.9.....3.....2.....86..1.......7.......7..1.34.1..84..7",
+            Technique::NakedPair,
+            &after_intersections(Technique::NakedPair),
+            "Naked Pair: r1c8<>1, r2c7<>1 [block 3]",
+        );
+    }
+
+    #[test]
+    fn naked_triple_in_row() {
+        assert_first_step_with(
+            ".....1..9...6...8.7.9.8.4..49......6..7..2..43...6......3...5...2.35...15.4.17.9.",
+            Technique::NakedTriple,
+            &after_intersections(Technique::NakedTriple),
+            "Naked Triple: r3c2<>3, r3c2<>5, r3c8<>2, r3c8<>3, r3c8<>5 [row 3]",
+        );
+    }
+
+    #[test]
+    fn naked_triple_in_col() {
+        assert_first_step_with(
+            ".....13.2...63..7.....9.8.491..6..4....5.....57......175.....9...63....81.....4..",
+            Technique::NakedTriple,
+            &after_intersections(Technique::NakedTriple),
+            "Naked Triple: r9c8<>5 [col 8]",
+        );
+    }
+
+    #[test]
+    fn naked_triple_in_block() {
+        assert_first_step_with(
+            "....5.8....5.1....6......9..8......64..2...3.3..5.9..4..7.2...1......4...4.7.36..",
+            Technique::NakedTriple,
+            &after_intersections(Technique::NakedTriple),
+            "Naked Triple: r1c8<>2, r1c8<>7, r2c8<>2, r2c8<>7, r3c7<>2, r3c7<>3, r3c7<>7, r3c9<>2, r3c9<>3, r3c9<>7 [block 3]",
+        );
+    }
+
+    #[test]
+    fn naked_quad_in_row() {
+        assert_first_step_with(
+            "2.....8.........23.45..9......58.6...1..9...2...4.6...1..6.......3.4.28.......51.",
+            Technique::NakedQuad,
+            &after_intersections(Technique::NakedQuad),
+            "Naked Quad: r5c1<>3, r5c1<>4, r5c1<>5, r5c1<>7, r5c3<>4, r5c3<>7 [row 5]",
+        );
+    }
+
+    #[test]
+    fn naked_quad_in_col() {
+        assert_first_step_with(
+            "79..13..6.....4.17...6..3..2.....7..6....9..3...78...........5..4..9...1.518.....",
+            Technique::NakedQuad,
+            &after_intersections(Technique::NakedQuad),
+            "Naked Quad: r3c1<>5, r6c1<>3, r6c1<>5, r6c1<>9 [col 1]",
+        );
+    }
+
+    #[test]
+    fn naked_quad_in_block() {
+        assert_first_step_with(
+            "....5.8....5.1....6......9..8......64..2...3.3..5.9..4..7.2...1......4...4.7.36..",
+            Technique::NakedQuad,
+            &after_intersections(Technique::NakedQuad),
+            "Naked Quad: r1c8<>2, r1c8<>7, r2c8<>2, r2c8<>7, r3c7<>2, r3c7<>3, r3c7<>5, r3c7<>7 [block 3]",
+        );
+    }
+
+    #[test]
+    fn hidden_pair_in_row() {
+        assert_first_step_with(
+            ".....1..9...6...8.7.9.8.4..49......6..7..2..43...6......3...5...2.35...15.4.17.9.",
+            Technique::HiddenPair,
+            &after_intersections(Technique::HiddenPair),
+            "Hidden Pair: r3c2<>3, r3c2<>5, r3c8<>2, r3c8<>3, r3c8<>5 [row 3]",
+        );
+    }
+
+    #[test]
+    fn hidden_pair_in_col() {
+        assert_first_step_with(
+            "7...1.....4..5.3...2...87.......4....8....61....185.2....59....87..3...5..1....9.",
+            Technique::HiddenPair,
+            &after_intersections(Technique::HiddenPair),
+            "Hidden Pair: r4c8<>5, r4c8<>8, r7c8<>4, r7c8<>6, r7c8<>8 [col 8]",
+        );
+    }
+
+    #[test]
+    fn hidden_pair_in_block() {
+        assert_first_step_with(
+            ".9...62..3..5...69......58.9.....3.....2.....86..1.......7.......7..1.34.1..84..7",
+            Technique::HiddenPair,
+            &after_intersections(Technique::HiddenPair),
+            "Hidden Pair: r1c8<>1, r2c7<>1 [block 3]",
+        );
+    }
+
+    #[test]
+    fn hidden_triple_in_row() {
+        assert_first_step_with(
+            "..8...4.....26..89...........35..9.7.1.3.....5....2..46.78......349..7..2....73..",
+            Technique::HiddenTriple,
+            &after_intersections(Technique::HiddenTriple),
+            "Hidden Triple: r2c1<>1, r2c2<>5, r2c6<>1, r2c6<>5 [row 2]",
+        );
+    }
+
+    #[test]
+    fn hidden_triple_in_col() {
+        assert_first_step_with(
+            "1.94....246.....3..2..6.......1...64.....5..79....81..2..8.3.....3........891....",
+            Technique::HiddenTriple,
+            &after_intersections(Technique::HiddenTriple),
+            "Hidden Triple: r5c3<>2, r6c3<>2, r6c3<>5, r6c3<>7, r7c3<>5, r7c3<>7 [col 3]",
+        );
+    }
+
+    #[test]
+    fn hidden_triple_in_block() {
+        assert_first_step_with(
+            "....5.8....5.1....6......9..8......64..2...3.3..5.9..4..7.2...1......4...4.7.36..",
+            Technique::HiddenTriple,
+            &after_intersections(Technique::HiddenTriple),
+            "Hidden Triple: r1c8<>2, r1c8<>7, r2c8<>2, r2c8<>7, r3c7<>2, r3c7<>3, r3c7<>5, r3c7<>7 [block 3]",
+        );
+    }
+
+    #[test]
+    fn hidden_quad_in_row() {
+        assert_first_step_with(
+            "2.....8.........23.45..9......58.6...1..9...2...4.6...1..6.......3.4.28.......51.",
+            Technique::HiddenQuad,
+            &after_intersections(Technique::HiddenQuad),
+            "Hidden Quad: r3c1<>7, r3c4<>1, r3c4<>7, r3c5<>1, r3c5<>7, r3c8<>7 [row 3]",
+        );
+    }
+
+    #[test]
+    fn hidden_quad_in_col() {
+        assert_first_step_with(
+            ".9...62..3..5...69......58.9.....3.....2.....86..1.......7.......7..1.34.1..84..7",
+            Technique::HiddenQuad,
+            &after_intersections(Technique::HiddenQuad),
+            "Hidden Quad: r4c9<>1, r5c9<>1, r7c9<>1 [col 9]",
+        );
+    }
+
+    #[test]
+    fn hidden_quad_in_block() {
+        assert_first_step_with(
+            "....5.8....5.1....6......9..8......64..2...3.3..5.9..4..7.2...1......4...4.7.36..",
+            Technique::HiddenQuad,
+            &after_intersections(Technique::HiddenQuad),
+            "Hidden Quad: r1c8<>2, r1c8<>7, r2c8<>2, r2c8<>7, r3c7<>2, r3c7<>3, r3c7<>7, r3c9<>2, r3c9<>3, r3c9<>7 [block 3]",
+        );
+    }
+}
@@ -0,0 +1,384 @@
+use crate::candidate_grid::{digit_bit, mask_digits, CandidateGrid};
+use crate::grid::{Cell, Digit, Unit, SIZE};
+use crate::solver::{SolveStep, StepAction, StepDetail, Technique};
+
+use super::{combinations, elimination_step};
+
+// 2行2列の4マスで、ちょうど2つのブロックにまたがるもの。
+// 並びは左上、右上、左下、右下なので、iの対角はcorners[3 - i]
+fn rectangles() -> Vec<[Cell; 4]> {
+    let mut rectangles: Vec<[Cell; 4]> = Vec::new();
+    for row1 in 0..SIZE {
+        for row2 in row1 + 1..SIZE {
+            for col1 in 0..SIZE {
+                for col2 in col1 + 1..SIZE {
+                    let corners = [
+                        Cell::new(row1, col1),
+                        Cell::new(row1, col2),
+                        Cell::new(row2, col1),
+                        Cell::new(row2, col2),
+                    ];
+                    let mut blocks: Vec<usize> = corners.iter().map(|x| x.block_index()).collect();
+                    blocks.sort_unstable();
+                    blocks.dedup();
+                    if blocks.len() == 2 {
+                        rectangles.push(corners);
+                    }
+                }
+            }
+        }
+    }
+    rectangles
+}
+
+// 2マスが共通に属するユニット
+fn shared_units(cell1: Cell, cell2: Cell) -> Vec<Unit> {
+    cell1.units().iter()
+        .filter(|x| x.contains(cell2))
+        .copied()
+        .collect()
+}
+
+// 長方形の2行と2列
+fn rectangle_units(corners: &[Cell; 4]) -> Vec<Unit> {
+    vec![
+        Unit::Row(corners[0].row_index),
+        Unit::Row(corners[3].row_index),
+        Unit::Col(corners[0].col_index),
+        Unit::Col(corners[3].col_index),
+    ]
+}
+
+fn rectangle_step(
+    technique: Technique,
+    candidates: &CandidateGrid,
+    eliminations: Vec<(Cell, Digit)>,
+    corners: &[Cell; 4],
+    mut digits: Vec<Digit>,
+    (floor, roof): (Vec<Cell>, Vec<Cell>),
+) -> Option<SolveStep> {
+    digits.sort();
+    elimination_step(technique, candidates, eliminations.into_iter(), rectangle_units(corners), corners.to_vec())
+        .map(|step| SolveStep {
+            detail: StepDetail::Rectangle { digits, corners: corners.to_vec(), floor, roof },
+            ..step
+        })
+}
+
+fn seen_by_all(cells: &[Cell]) -> impl Iterator<Item = Cell> + '_ {
+    Cell::all().filter(move |x| cells.iter().all(|y| x.sees(*y)))
+}
+
+// 4マスに同じ2つの候補{a,b}しか残らないと解が2つになる (デッドリーパターン) ので、
+// それを避ける候補を真とする。floorは{a,b}だけを持つマス、roofはそれ以外を持つマス
+pub(super) fn find_unique_rectangle(candidates: &CandidateGrid, ur_type: usize, technique: Technique) -> Option<SolveStep> {
+    for corners in rectangles() {
+        if !corners.iter().all(|x| candidates.is_empty_cell(*x)) {
+            continue;
+        }
+        let common = corners.iter().fold(0xffff, |acc, x| acc & candidates.mask(*x));
+        let digits: Vec<Digit> = mask_digits(common).collect();
+
+        for pair in combinations(&digits, 2) {
+            let pair_mask = digit_bit(pair[0]) | digit_bit(pair[1]);
+            let floor: Vec<Cell> = corners.iter().filter(|x| candidates.mask(**x) == pair_mask).copied().collect();
+            let roof: Vec<Cell> = corners.iter().filter(|x| candidates.mask(**x) != pair_mask).copied().collect();
+            let extra = roof.iter().fold(0, |acc, x| acc | candidates.mask(*x)) & !pair_mask;
+            let is_roof_side = roof.len() == 2 && !shared_units(roof[0], roof[1]).is_empty();
+
+            let eliminations: Vec<(Cell, Digit)> = match ur_type {
+                1 if floor.len() == 3 => {
+                    pair.iter().map(|x| (roof[0], *x)).collect()
+                }
+                2 if is_roof_side && extra.count_ones() == 1 && roof.iter().all(|x| candidates.mask(*x) == pair_mask | extra) => {
+                    seen_by_all(&roof).flat_map(|x| mask_digits(extra).map(move |digit| (x, digit))).collect()
+                }
+                3 if is_roof_side => {
+                    unique_rectangle_type3(candidates, &roof, extra)
+                }
+                4 if is_roof_side => {
+                    shared_units(roof[0], roof[1]).iter()
+                        .flat_map(|unit| {
+                            [(pair[0], pair[1]), (pair[1], pair[0])].iter()
+                                .filter(|(x, _)| {
+                                    let cells = candidates.cells_with(*unit, *x);
+                                    cells.len() == 2 && cells.iter().all(|cell| roof.contains(cell))
+                                })
+                                .flat_map(|(_, y)| roof.iter().map(move |cell| (*cell, *y)))
+                                .collect::<Vec<(Cell, Digit)>>()
+                        })
+                        .collect()
+                }
+                5 if !floor.is_empty() && !is_roof_side && extra.count_ones() == 1 && roof.iter().all(|x| candidates.mask(*x) == pair_mask | extra) => {
+                    seen_by_all(&roof).flat_map(|x| mask_digits(extra).map(move |digit| (x, digit))).collect()
+                }
+                6 if floor.len() == 2 && !is_roof_side => {
+                    let rows = [Unit::Row(corners[0].row_index), Unit::Row(corners[3].row_index)];
+                    let cols = [Unit::Col(corners[0].col_index), Unit::Col(corners[3].col_index)];
+                    pair.iter()
+                        .filter(|x| {
+                            rows.iter().all(|unit| candidates.positions(*unit, **x).count_ones() == 2)
+                                || cols.iter().all(|unit| candidates.positions(*unit, **x).count_ones() == 2)
+                        })
+                        .flat_map(|x| roof.iter().map(move |cell| (*cell, *x)))
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+
+            let step = rectangle_step(technique, candidates, eliminations, &corners, pair, (floor, roof));
+            if step.is_some() {
+                return step;
+            }
+        }
+    }
+
+    None
+}
+
+// roofの2マスのどちらかにはextraの数字が入るので、roofを1つの仮想的なマスとみなして
+// 共通のユニットで他のマスと組み合わせたネイキッドサブセットを探す
+fn unique_rectangle_type3(candidates: &CandidateGrid, roof: &[Cell], extra: u16) -> Vec<(Cell, Digit)> {
+    for unit in shared_units(roof[0], roof[1]) {
+        let others: Vec<Cell> = unit.cells()
+            .filter(|x| !roof.contains(x) && candidates.count(*x) >= 1)
+            .collect();
+        for size in 1..=3 {
+            for subset in combinations(&others, size) {
+                let mask = subset.iter().fold(extra, |acc, x| acc | candidates.mask(*x));
+                if mask.count_ones() as usize != size + 1 {
+                    continue;
+                }
+                let eliminations: Vec<(Cell, Digit)> = others.iter()
+                    .filter(|x| !subset.contains(x))
+                    .flat_map(|x| mask_digits(mask).map(move |digit| (*x, digit)))
+                    .filter(|(x, digit)| candidates.has(*x, *digit))
+                    .collect();
+                if !eliminations.is_empty() {
+                    return eliminations;
+                }
+            }
+        }
+    }
+
+    Vec::new()
+}
+
+// 問題のヒントでない確定済みのマスでデッドリーパターンができるのを避ける。
+// 3マスが確定していれば残りのマスの数字を、2マスが確定していれば残り2マスに共通する余分な数字を使う
+pub(super) fn find_avoidable_rectangle(candidates: &CandidateGrid) -> Option<SolveStep> {
+    let value = |cell: Cell| candidates.grid().get(cell.row_index, cell.col_index);
+    for corners in rectangles() {
+        if corners.iter().any(|x| candidates.is_given(*x)) {
+            continue;
+        }
+
+        let empty: Vec<usize> = (0..4).filter(|x| candidates.is_empty_cell(corners[*x])).collect();
+        let (eliminations, digits): (Vec<(Cell, Digit)>, Vec<Digit>) = match empty.len() {
+            1 => {
+                let target = empty[0];
+                let sides: Vec<Option<Digit>> = (0..4)
+                    .filter(|x| *x != target && *x != 3 - target)
+                    .map(|x| value(corners[x]))
+                    .collect();
+                match (value(corners[3 - target]), sides[0] == sides[1]) {
+                    (Some(digit), true) if sides[0] != Some(digit) => (vec![(corners[target], digit)], vec![digit, sides[0].unwrap()]),
+                    _ => (Vec::new(), Vec::new()),
+                }
+            }
+            2 if empty[0] + empty[1] != 3 => {
+                // 確定した2マスと、それぞれの反対側の空きマス
+                let solved: Vec<usize> = (0..4).filter(|x| !empty.contains(x)).collect();
+                let opposite = |x: usize| match empty.contains(&(x ^ 1)) {
+                    true => x ^ 1,
+                    false => x ^ 2,
+                };
+                match (value(corners[solved[0]]), value(corners[solved[1]])) {
+                    (Some(digit1), Some(digit2)) if digit1 != digit2 => {
+                        let (cell1, cell2) = (corners[opposite(solved[0])], corners[opposite(solved[1])]);
+                        let (mask1, mask2) = (candidates.mask(cell1), candidates.mask(cell2));
+                        let extra1 = mask1 & !digit_bit(digit2);
+                        let extra2 = mask2 & !digit_bit(digit1);
+                        let is_pattern = mask1 & digit_bit(digit2) != 0
+                            && mask2 & digit_bit(digit1) != 0
+                            && extra1 == extra2
+                            && extra1.count_ones() == 1;
+                        match is_pattern {
+                            true => {
+                                let eliminations = seen_by_all(&[cell1, cell2]).flat_map(|x| mask_digits(extra1).map(move |digit| (x, digit))).collect();
+                                (eliminations, vec![digit1, digit2])
+                            }
+                            false => (Vec::new(), Vec::new()),
+                        }
+                    }
+                    _ => (Vec::new(), Vec::new()),
+                }
+            }
+            _ => (Vec::new(), Vec::new()),
+        };
+
+        let solved: Vec<Cell> = corners.iter().filter(|x| !candidates.is_empty_cell(**x)).copied().collect();
+        let unsolved: Vec<Cell> = empty.iter().map(|x| corners[*x]).collect();
+        let step = rectangle_step(Technique::AvoidableRectangle, candidates, eliminations, &corners, digits, (solved, unsolved));
+        if step.is_some() {
+            return step;
+        }
+    }
+
+    None
+}
+
+// 3択のマスが1つだけで残りがすべて2択なら、3択のマスには行・列・ブロックで3回現れる数字が入る
+pub(super) fn find_bug_plus_one(candidates: &CandidateGrid) -> Option<SolveStep> {
+    let empty_cells: Vec<Cell> = Cell::all().filter(|x| candidates.is_empty_cell(*x)).collect();
+    let triple_cells: Vec<Cell> = empty_cells.iter().filter(|x| candidates.count(**x) != 2).copied().collect();
+    if triple_cells.len() != 1 || candidates.count(triple_cells[0]) != 3 {
+        return None;
+    }
+
+    let cell = triple_cells[0];
+    let digit = candidates.digits(cell)
+        .find(|x| cell.units().iter().all(|unit| candidates.positions(*unit, *x).count_ones() == 3))?;
+    let is_bug = Unit::all().all(|unit| {
+        Digit::all().all(|x| {
+            let count = candidates.positions(unit, x).count_ones();
+            count == 0 || count == 2 || (count == 3 && x == digit && unit.contains(cell))
+        })
+    });
+    if !is_bug {
+        return None;
+    }
+
+    Some(SolveStep {
+        technique: Technique::BugPlusOne,
+        action: StepAction::Place(cell, digit),
+        units: cell.units().to_vec(),
+        cells: vec![cell],
+        detail: StepDetail::None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtrack::{solve_exact, SolveResult};
+    use crate::grid::Grid;
+    use crate::solver::{solve_with_options, SolverOptions};
+    use crate::techniques::{assert_first_step_with, find_step};
+
+    const TYPE1: &str = "..3.197.....2.7.3.8.........2..35...6.....2..9..6.......57..62......4...7...6.95.";
+
+    fn options() -> SolverOptions {
+        SolverOptions::default().with_uniqueness(true)
+    }
+
+    #[test]
+    fn unique_rectangle_type1() {
+        assert_first_step_with(
+            TYPE1,
+            Technique::UniqueRectangleType1,
+            &options(),
+            "Unique Rectangle Type 1: r6c9<>3, r6c9<>5 [digits 3, 5; corners r5c2, r5c9, r6c2, r6c9; floor r5c2, r5c9, r6c2; roof r6c9]",
+        );
+    }
+
+    #[test]
+    fn unique_rectangle_type2() {
+        assert_first_step_with(
+            ".....7..42.......8..3.8.5......65.973.2.....5...4...6.6...5.....491.3......8..1..",
+            Technique::UniqueRectangleType2,
+            &options(),
+            "Unique Rectangle Type 2: r3c9<>9, r7c7<>9 [digits 2, 3; corners r7c2, r7c9, r9c2, r9c9; floor r7c2, r9c2; roof r7c9, r9c9]",
+        );
+    }
+
+    #[test]
+    fn unique_rectangle_type3() {
+        assert_first_step_with(
+            "7.....3...9.1.7..5....4..7.5...24.3...3...5....2.6.....8..1.62...5..6.91.....87..",
+            Technique::UniqueRectangleType3,
+            &options(),
+            "Unique Rectangle Type 3: r7c9<>4 [digits 3, 5; corners r6c4, r6c6, r7c4, r7c6; floor r6c4, r6c6; roof r7c4, r7c6]",
+        );
+    }
+
+    #[test]
+    fn unique_rectangle_type4() {
+        assert_first_step_with(
+            ".9...62..3..5...69......58.9.....3.....2.....86..1.......7.......7..1.34.1..84..7",
+            Technique::UniqueRectangleType4,
+            &options(),
+            "Unique Rectangle Type 4: r1c4<>3, r3c4<>3 [digits 1, 3; corners r1c4, r1c9, r3c4, r3c9; floor r1c9, r3c9; roof r1c4, r3c4]",
+        );
+    }
+
+    #[test]
+    fn unique_rectangle_type5() {
+        assert_first_step_with(
+            "9.2.....3...1.....7.3.89..5.68.3............22...6..38..48...7.....95....5.6.4...",
+            Technique::UniqueRectangleType5,
+            &options(),
+            "Unique Rectangle Type 5: r2c8<>4, r3c8<>4 [digits 1, 8; corners r1c7, r1c8, r8c7, r8c8; floor r8c7; roof r1c7, r1c8, r8c8]",
+        );
+    }
+
+    #[test]
+    fn unique_rectangle_type6() {
+        assert_first_step_with(
+            ".1.34....7.9............5.6..5.617....2...8.1.8...7.9...1..5.6245......8....9....",
+            Technique::UniqueRectangleType6,
+            &options(),
+            "Unique Rectangle Type 6: r4c2<>9, r7c1<>9 [digits 3, 9; corners r4c1, r4c2, r7c1, r7c2; floor r4c1, r7c2; roof r4c2, r7c1]",
+        );
+    }
+
+    // 確定したマスでできる長方形はシングルで先に埋まることが多いので、1手ごとに直接探す
+    #[test]
+    fn avoidable_rectangle() {
+        let grid: Grid = "..7...4.2...9.....51....9.....742........86....4....5..9.813..7.7......5..3....1.".parse().unwrap();
+        let solution = match solve_exact(&grid) {
+            SolveResult::Solved(solution) => solution,
+            result => panic!("not a proper puzzle: {:?}", result),
+        };
+        let options = SolverOptions::default();
+        let mut candidates = CandidateGrid::new(&grid);
+        let step = loop {
+            if let Some(step) = find_avoidable_rectangle(&candidates) {
+                break step;
+            }
+            let step = Technique::all().iter()
+                .filter(|x| options.is_enabled(**x))
+                .find_map(|x| find_step(*x, &candidates, &options))
+                .unwrap();
+            candidates.apply(&step);
+        };
+
+        assert_eq!(
+            step.to_string(),
+            "Avoidable Rectangle: r3c5<>6 [digits 2, 6; corners r2c3, r2c5, r3c3, r3c5; floor r2c3, r2c5, r3c3; roof r3c5]",
+        );
+        assert_ne!(solution.get(2, 4), Digit::new(6));
+    }
+
+    #[test]
+    fn bug_plus_one() {
+        assert_first_step_with(
+            ".8.9......967.1..2..7........2....6.1.....5.86....54.7..8....56....9.......1.3.7.",
+            Technique::BugPlusOne,
+            &options(),
+            "BUG+1: r3c1=2 [row 3, col 1, block 1]",
+        );
+    }
+
+    // allow_uniquenessを指定しなければ、唯一解を前提にする手法は使わない
+    #[test]
+    fn requires_allow_uniqueness() {
+        let options = SolverOptions::default().with(Technique::UniqueRectangleType1);
+        assert_eq!(options.techniques.len(), Technique::all().len());
+        assert!(!options.is_enabled(Technique::UniqueRectangleType1));
+
+        let trace = solve_with_options(&TYPE1.parse().unwrap(), &options);
+        assert!(trace.steps.iter().all(|x| !x.technique.requires_uniqueness()));
+        assert!(options.with_uniqueness(true).is_enabled(Technique::UniqueRectangleType1));
+    }
+}
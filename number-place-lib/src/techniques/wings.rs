@@ -0,0 +1,159 @@
+use crate::candidate_grid::{digit_bit, mask_digits, CandidateGrid};
+use crate::grid::{Cell, Unit};
+use crate::solver::{SolveStep, StepDetail, Technique};
+
+use super::elimination_step;
+
+fn cells_with_count(candidates: &CandidateGrid, count: u32) -> Vec<Cell> {
+    Cell::all()
+        .filter(|x| candidates.count(*x) == count)
+        .collect()
+}
+
+// seen_byのすべてから見えるマスからmaskの数字を除外する
+fn wing_step(
+    technique: Technique,
+    candidates: &CandidateGrid,
+    mask: u16,
+    pivots: Vec<Cell>,
+    pincers: Vec<Cell>,
+    seen_by: &[Cell],
+) -> Option<SolveStep> {
+    let eliminations = Cell::all()
+        .filter(|x| seen_by.iter().all(|y| x.sees(*y)))
+        .flat_map(|x| mask_digits(mask).map(move |digit| (x, digit)));
+    let cells: Vec<Cell> = pivots.iter().chain(pincers.iter()).copied().collect();
+    elimination_step(technique, candidates, eliminations, Vec::new(), cells)
+        .map(|step| SolveStep {
+            detail: StepDetail::Wing { pivots, pincers },
+            ..step
+        })
+}
+
+// 候補{x,y}の軸から見える{x,z}と{y,z}のマスのどちらかは必ずzになるので、
+// 両方から見えるマスからzを除外する
+pub(super) fn find_xy_wing(candidates: &CandidateGrid) -> Option<SolveStep> {
+    let bivalue_cells = cells_with_count(candidates, 2);
+    for pivot in bivalue_cells.iter() {
+        let pivot_mask = candidates.mask(*pivot);
+        let wings: Vec<Cell> = bivalue_cells.iter()
+            .filter(|x| pivot.sees(**x))
+            .filter(|x| (candidates.mask(**x) & pivot_mask).count_ones() == 1)
+            .copied()
+            .collect();
+
+        for (i, pincer1) in wings.iter().enumerate() {
+            for pincer2 in wings.iter().skip(i + 1) {
+                let (mask1, mask2) = (candidates.mask(*pincer1), candidates.mask(*pincer2));
+                let z = mask1 & mask2 & !pivot_mask;
+                if z == 0 || mask1 | mask2 != pivot_mask | z {
+                    continue;
+                }
+                let step = wing_step(Technique::XYWing, candidates, z, vec![*pivot], vec![*pincer1, *pincer2], &[*pincer1, *pincer2]);
+                if step.is_some() {
+                    return step;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// 候補{x,y,z}の軸と、軸から見える{x,z}と{y,z}のマスのいずれかは必ずzになるので、
+// 3つすべてから見えるマスからzを除外する
+pub(super) fn find_xyz_wing(candidates: &CandidateGrid) -> Option<SolveStep> {
+    let bivalue_cells = cells_with_count(candidates, 2);
+    for pivot in cells_with_count(candidates, 3).iter() {
+        let pivot_mask = candidates.mask(*pivot);
+        let wings: Vec<Cell> = bivalue_cells.iter()
+            .filter(|x| pivot.sees(**x))
+            .filter(|x| candidates.mask(**x) & !pivot_mask == 0)
+            .copied()
+            .collect();
+
+        for (i, pincer1) in wings.iter().enumerate() {
+            for pincer2 in wings.iter().skip(i + 1) {
+                let (mask1, mask2) = (candidates.mask(*pincer1), candidates.mask(*pincer2));
+                let z = mask1 & mask2;
+                if z.count_ones() != 1 {
+                    continue;
+                }
+                let step = wing_step(Technique::XYZWing, candidates, z, vec![*pivot], vec![*pincer1, *pincer2], &[*pivot, *pincer1, *pincer2]);
+                if step.is_some() {
+                    return step;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// 同じ候補{x,y}を持つ2マスが、xの強リンク (ユニット内でxの入る2マス) の両端からそれぞれ見えていれば、
+// 2マスのどちらかは必ずyになるので、両方から見えるマスからyを除外する
+pub(super) fn find_w_wing(candidates: &CandidateGrid) -> Option<SolveStep> {
+    let bivalue_cells = cells_with_count(candidates, 2);
+    for (i, pincer1) in bivalue_cells.iter().enumerate() {
+        let mask = candidates.mask(*pincer1);
+        for pincer2 in bivalue_cells.iter().skip(i + 1) {
+            if candidates.mask(*pincer2) != mask || pincer1.sees(*pincer2) {
+                continue;
+            }
+
+            for x in mask_digits(mask) {
+                for unit in Unit::all() {
+                    let link = candidates.cells_with(unit, x);
+                    if link.len() != 2 || link.contains(pincer1) || link.contains(pincer2) {
+                        continue;
+                    }
+                    let is_connected = (link[0].sees(*pincer1) && link[1].sees(*pincer2))
+                        || (link[0].sees(*pincer2) && link[1].sees(*pincer1));
+                    if !is_connected {
+                        continue;
+                    }
+                    let y = mask & !digit_bit(x);
+                    let step = wing_step(Technique::WWing, candidates, y, link, vec![*pincer1, *pincer2], &[*pincer1, *pincer2]);
+                    if step.is_some() {
+                        return step;
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Technique;
+    use crate::techniques::assert_first_step;
+
+    #[test]
+    fn xy_wing() {
+        assert_first_step(
+            "..2...9......498.1.......25.15.8.......7.1...7...6....4....3.9.......6.267.12.4..",
+            Technique::XYWing,
+            "XY-Wing: r9c8<>8, r9c9<>8 [pivot r7c5; pincers r7c9, r9c6]",
+        );
+    }
+
+    #[test]
+    fn xyz_wing() {
+        assert_first_step(
+            "59.6.1....8...2.1....3...6.......5..87......4....73...942.1.8....74....21....9...",
+            Technique::XYZWing,
+            "XYZ-Wing: r8c6<>5 [pivot r7c6; pincers r5c6, r7c4]",
+        );
+    }
+
+    #[test]
+    fn w_wing() {
+        assert_first_step(
+            "9.7..6.1......7.8..1..4.3..8..42.6..2....1..3..1....9..9..78.....6..45..5........",
+            Technique::WWing,
+            "W-Wing: r1c9<>4, r7c7<>4, r9c7<>4 [pivot r6c7, r6c9; pincers r1c7, r7c9]",
+        );
+    }
+}
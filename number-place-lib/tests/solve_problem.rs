@@ -0,0 +1,129 @@
+use number_place_lib::{solve_problem, Grid, SudokuExactCover};
+
+// 既知の問題と解。前半はシングルとサブセットだけで解き切れるもの、後半は途中までしか解けないもの
+const CORPUS: &[(&str, &str)] = &[
+    (
+        "530070000600195000098000060800060003400803001700020006060000280000419005000080079",
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179",
+    ),
+    (
+        "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......",
+        "417369825632158947958724316825437169791586432346912758289643571573291684164875293",
+    ),
+    (
+        "52...6.........7.13...........4..8..6......5...........418.........3..2...87.....",
+        "527316489896542731314987562172453896689271354453698217941825673765134928238769145",
+    ),
+    (
+        "6.....8.3.4.7.................5.4.7.3..2.....1.6.......2.....5.....8.6......1....",
+        "617459823248736915539128467982564371374291586156873294823647159791385642465912738",
+    ),
+    (
+        "..3..5...8..4..29.7.4..6..........52..5...6..69......3..72..8.53...7...6.....4.7.",
+        "923185764856437291714926538478361952235849617691752483167293845349578126582614379",
+    ),
+    (
+        ".6.47.3...95.3.8.......8..4.3....261..6....5...185...3...9.....7.............2..6",
+        "862479315495631827317528694538794261946213758271856943654987132723165489189342576",
+    ),
+    (
+        ".....7...8......676..9..8..1...4...5.3..5......27............36.943......7...5..1",
+        "945687123823514967617932854169248375738156249452793618281479536594361782376825491",
+    ),
+    (
+        ".2.....7......4...9......8615..6..3..........8..5419...7..9.86...4.7.3..3..6.8...",
+        "528936174761824593943715286157269438492387615836541927275493861684172359319658742",
+    ),
+    (
+        "800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+        "812753649943682175675491283154237896369845721287169534521974368438526917796318452",
+    ),
+    (
+        "........2.679......85...6.4..4...5..6..1.72.31..6............4.....9.1..7..3.2..6",
+        "413568792267934815985271634374829561659147283128653479592716348836495127741382956",
+    ),
+    (
+        ".53.81...1...3.2.............59.6...26..4.93.4.....6..8..3.2.6......8.....175..43",
+        "953281476146537298782694315315926784267845931498173652874312569539468127621759843",
+    ),
+    (
+        ".........4215.........3....3...65.876.......5.7.4....6..91...7.2......9.8.....24.",
+        "753694812421587369986231754342965187698713425175428936539142678214876593867359241",
+    ),
+    (
+        ".29.........7.93.....6.....4....8.6.....9.4...9...5..8..6....5....5.6..19.324.8..",
+        "729853614564719382831624795412378569685192437397465128146987253278536941953241876",
+    ),
+    (
+        "3.......841..2.65..625...4....93..................136.8.7...5....34.........1.8.2",
+        "375164928418329657962578143546937281231856479789241365827693514153482796694715832",
+    ),
+    (
+        "1.26...3....1...5.83.2.7.....69...7......56...94...1.......6....614.3...7.3....6.",
+        "152684937647139852839257416316942578278315649594768123425876391961423785783591264",
+    ),
+];
+
+const FULLY_SOLVED: usize = 8;
+
+fn parse(text: &str) -> Grid {
+    text.parse().unwrap()
+}
+
+// 解いた盤面の埋まったマスが、すべてsolutionsのどれとも一致する
+fn assert_consistent(problem: &Grid, solved: &Grid, solutions: &[Grid]) {
+    for (row_index, col_index, value) in solved.cells() {
+        if let Some(given) = problem.get(row_index, col_index) {
+            assert_eq!(value, Some(given), "given changed at r{}c{}", row_index + 1, col_index + 1);
+        }
+        if value.is_none() {
+            continue;
+        }
+        for solution in solutions {
+            assert_eq!(value, solution.get(row_index, col_index), "wrong digit at r{}c{} in {}", row_index + 1, col_index + 1, problem);
+        }
+    }
+}
+
+#[test]
+fn never_places_a_wrong_digit() {
+    for (problem, solution) in CORPUS {
+        let problem = parse(problem);
+        let solved = Grid::from_lines(&solve_problem(&problem.to_lines())).unwrap();
+        assert_consistent(&problem, &solved, &[parse(solution)]);
+    }
+}
+
+#[test]
+fn solves_puzzles_within_singles_and_subsets() {
+    for (problem, solution) in CORPUS.iter().take(FULLY_SOLVED) {
+        let solved = solve_problem(&parse(problem).to_lines());
+        assert_eq!(solved, parse(solution).to_lines(), "not solved: {}", problem);
+    }
+}
+
+// 解が複数ある問題では、すべての解で共通する数字しか置かない
+#[test]
+fn places_only_common_digits_without_unique_solution() {
+    let mut problem = parse(CORPUS[0].0);
+    for col_index in 0..3 {
+        problem.set(0, col_index, None);
+        problem.set(8, 8 - col_index, None);
+    }
+    let solutions = SudokuExactCover::new(&problem, &[]).solutions(100);
+    assert!(solutions.len() > 1);
+
+    let solved = Grid::from_lines(&solve_problem(&problem.to_lines())).unwrap();
+    assert!(solved.count_empty() < problem.count_empty());
+    assert_consistent(&problem, &solved, &solutions);
+}
+
+#[test]
+fn leaves_empty_and_conflicting_grids_unchanged() {
+    let empty = Grid::empty().to_lines();
+    assert_eq!(solve_problem(&empty), empty);
+
+    let mut conflicting = parse(CORPUS[0].0);
+    conflicting.set(0, 2, conflicting.get(0, 0));
+    assert_eq!(solve_problem(&conflicting.to_lines()), conflicting.to_lines());
+}
@@ -4,75 +4,326 @@ use std::fs::{read_dir, File};
 use std::path::{Path, PathBuf};
 use std::io::{BufReader, BufWriter, Write};
 use std::ffi::OsStr;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use rand::seq::SliceRandom;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use clap::{App, Arg};
 
-struct Cell {
-    row_index: usize,
-    col_index: usize,
-    value: i32,
-}
-
-fn fit_template(pattern_lines: &Vec<Vec<i32>>, template_lines: &Vec<Vec<i32>>) -> Vec<Vec<i32>> {
-    let mut fit_lines: Vec<Vec<i32>> = Vec::new();
-    for row_zip in pattern_lines.iter().zip(template_lines.iter()) {
-        let (pattern_row, template_row) = row_zip;
-        let mut fit_row: Vec<i32> = Vec::new();
-        for col_zip in pattern_row.iter().zip(template_row.iter()) {
-            let (pattern_col, template_col) = col_zip;
-            fit_row.push(match template_col {
-                1 => *pattern_col,
-                _ => 0,
-            });
+use serde::{Deserialize, Serialize};
+
+use number_place_lib::{Difficulty, Grid, Rating};
+
+// ヒントを消すときの対称性。horizontalは左右対称、verticalは上下対称、diagonalは左上から右下の対角線で対称
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Symmetry {
+    None,
+    Rotational180,
+    Rotational90,
+    Horizontal,
+    Vertical,
+    Diagonal,
+    Dihedral,
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Symmetry, String> {
+        match s {
+            "none" => Ok(Symmetry::None),
+            "rot180" => Ok(Symmetry::Rotational180),
+            "rot90" => Ok(Symmetry::Rotational90),
+            "horizontal" => Ok(Symmetry::Horizontal),
+            "vertical" => Ok(Symmetry::Vertical),
+            "diagonal" => Ok(Symmetry::Diagonal),
+            "dihedral" => Ok(Symmetry::Dihedral),
+            _ => Err(format!("unknown symmetry: {}", s)),
         }
-        fit_lines.push(fit_row);
     }
+}
+
+impl Symmetry {
+    // 対称性で互いに移り合うマスの組 (軌道)
+    fn orbit(self, row_index: usize, col_index: usize) -> Vec<(usize, usize)> {
+        let (r, c) = (row_index, col_index);
+        let mut cells = match self {
+            Symmetry::None => vec![(r, c)],
+            Symmetry::Rotational180 => vec![(r, c), (8 - r, 8 - c)],
+            Symmetry::Rotational90 => vec![(r, c), (c, 8 - r), (8 - r, 8 - c), (8 - c, r)],
+            Symmetry::Horizontal => vec![(r, c), (r, 8 - c)],
+            Symmetry::Vertical => vec![(r, c), (8 - r, c)],
+            Symmetry::Diagonal => vec![(r, c), (c, r)],
+            Symmetry::Dihedral => vec![
+                (r, c), (c, 8 - r), (8 - r, 8 - c), (8 - c, r),
+                (r, 8 - c), (8 - r, c), (c, r), (8 - c, 8 - r),
+            ],
+        };
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+}
 
-    fit_lines
+// パターンファイルは数字の配列のみの形式とシードを含む形式のどちらも読める
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PatternFile {
+    Lines(Vec<Vec<i32>>),
+    WithMetadata { pattern: Vec<Vec<i32>> },
 }
 
-fn contains_zero(lines: &Vec<Vec<i32>>) -> bool {
-    let mut zero_exists = false;
-    for row in lines {
-        zero_exists = row.iter().any(|x| x == &0);
-        if zero_exists {
-            break;
+impl PatternFile {
+    fn lines(&self) -> &[Vec<i32>] {
+        match self {
+            PatternFile::Lines(lines) => lines,
+            PatternFile::WithMetadata { pattern } => pattern,
         }
     }
-    zero_exists
 }
 
-fn is_solvable(problem_lines: &Vec<Vec<i32>>) -> bool {
-    let lines: Vec<Vec<i32>> = number_place_lib::solve_problem(problem_lines);
-    !contains_zero(&lines)
+#[derive(Serialize)]
+struct RatingInfo {
+    difficulty: String,
+    score: f32,
+    hardest: Option<String>,
+    steps: usize,
 }
 
-fn add_blank_to_template(template_lines: &Vec<Vec<i32>>, number_of_blanks: usize) -> Vec<Vec<i32>> {
-    let mut cells: Vec<Cell> = Vec::new();
-    for (row_index, row) in template_lines.iter().enumerate() {
-        for (col_index, col) in row.iter().enumerate() {
-            cells.push(Cell{
-                row_index: row_index,
-                col_index: col_index,
-                value: *col,
-            });
+impl From<&Rating> for RatingInfo {
+    fn from(rating: &Rating) -> RatingInfo {
+        RatingInfo {
+            difficulty: rating.difficulty.to_string(),
+            score: rating.score,
+            hardest: rating.hardest.map(|x| x.to_string()),
+            steps: rating.steps,
         }
     }
+}
+
+// 目標とする難易度。difficultyと点数の範囲はどちらか一方または両方を指定する
+struct Target {
+    difficulty: Option<Difficulty>,
+    min_score: Option<f32>,
+    max_score: Option<f32>,
+}
+
+impl Target {
+    // 盤面からさらにヒントを減らしてもよいか (難しすぎないか)
+    fn allows(&self, rating: &Rating) -> bool {
+        let is_difficulty_allowed = match self.difficulty {
+            Some(difficulty) => rating.difficulty <= difficulty,
+            None => rating.difficulty != Difficulty::Unsolvable,
+        };
+        let is_score_allowed = self.max_score.is_none_or(|x| rating.score <= x);
+        is_difficulty_allowed && is_score_allowed
+    }
+
+    fn accepts(&self, rating: &Rating) -> bool {
+        let is_difficulty_accepted = self.difficulty.is_none_or(|x| rating.difficulty == x);
+        let is_score_accepted = self.min_score.is_none_or(|x| rating.score >= x);
+        self.allows(rating) && is_difficulty_accepted && is_score_accepted
+    }
+}
+
+struct Budget {
+    max_attempts: usize,
+    time_limit: Duration,
+}
+
+// テンプレートの値: 0は常に空きマス、1は空きマスにしてよいヒント、2は必ず残すヒント
+const TEMPLATE_BLANK: i32 = 0;
+const TEMPLATE_REMOVABLE: i32 = 1;
+const TEMPLATE_FIXED: i32 = 2;
+
+fn default_template() -> Vec<Vec<i32>> {
+    vec![vec![TEMPLATE_REMOVABLE; 9]; 9]
+}
+
+fn load_template(template_path: &Path) -> Vec<Vec<i32>> {
+    let template_file = File::open(template_path).unwrap();
+    let template_lines: Vec<Vec<i32>> = serde_json::from_reader(BufReader::new(template_file)).unwrap();
+
+    let is_valid = template_lines.len() == 9
+        && template_lines.iter().all(|row| row.len() == 9)
+        && template_lines.iter().flatten().all(|x| [TEMPLATE_BLANK, TEMPLATE_REMOVABLE, TEMPLATE_FIXED].contains(x));
+    if !is_valid {
+        panic!("{}: template must be 9x9 and contain only 0, 1 or 2", template_path.display());
+    }
+
+    template_lines
+}
+
+fn template_name(template_path: &Path) -> String {
+    template_path.file_name().unwrap().to_string_lossy().into_owned()
+}
 
-    let mut filled_cells: Vec<&Cell> = cells.iter()
-        .filter(|x| x.value == 1)
+fn json_paths(dir_path: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = read_dir(dir_path).unwrap()
+        .map(|res| res.unwrap().path())
+        .filter(|path| !path.is_dir() && path.extension().unwrap_or(OsStr::new("")) == "json")
         .collect();
-    let mut rng = rand::thread_rng();
-    filled_cells.shuffle(&mut rng);
+    paths.sort();
+    paths
+}
+
+fn fit_template(pattern: &Grid, template_lines: &[Vec<i32>]) -> Grid {
+    let mut fit_grid = Grid::empty();
+    for (row_index, template_row) in template_lines.iter().enumerate() {
+        for (col_index, template_col) in template_row.iter().enumerate() {
+            fit_grid.set(row_index, col_index, match *template_col {
+                TEMPLATE_BLANK => None,
+                _ => pattern.get(row_index, col_index),
+            });
+        }
+    }
+
+    fit_grid
+}
+
+fn is_solvable(problem: &Grid) -> bool {
+    let grid = number_place_lib::solve_grid(problem);
+    grid.count_empty() == 0
+}
+
+fn is_proper(problem: &Grid) -> bool {
+    is_solvable(problem) && number_place_lib::has_unique_solution(problem)
+}
+
+// 軌道のすべてのマスが空きマスにしてよいヒントであるものだけを返す
+fn removable_orbits(template_lines: &[Vec<i32>], symmetry: Symmetry) -> Vec<Vec<(usize, usize)>> {
+    let mut orbits: Vec<Vec<(usize, usize)>> = Vec::new();
+    for (row_index, row) in template_lines.iter().enumerate() {
+        for col_index in 0..row.len() {
+            let orbit = symmetry.orbit(row_index, col_index);
+            let is_removable = orbit.iter().all(|(r, c)| template_lines[*r][*c] == TEMPLATE_REMOVABLE);
+            if is_removable && !orbits.contains(&orbit) {
+                orbits.push(orbit);
+            }
+        }
+    }
+    orbits
+}
+
+// 対称性を保つため、軌道単位でnumber_of_blanks個以上のマスを空ける
+fn add_blank_to_template(template_lines: &[Vec<i32>], number_of_blanks: usize, symmetry: Symmetry, rng: &mut impl Rng) -> Vec<Vec<i32>> {
+    let mut orbits = removable_orbits(template_lines, symmetry);
+    orbits.shuffle(rng);
 
-    let updated_lines: Vec<Vec<i32>> = filled_cells.iter()
-        .take(number_of_blanks)
-        .fold(template_lines.clone(), |mut acc, x| { acc[x.row_index][x.col_index] = 0; acc});
+    let mut updated_lines: Vec<Vec<i32>> = template_lines.to_vec();
+    let mut blank_count = 0;
+    for orbit in orbits {
+        if blank_count >= number_of_blanks {
+            break;
+        }
+        for (row_index, col_index) in orbit {
+            updated_lines[row_index][col_index] = TEMPLATE_BLANK;
+            blank_count += 1;
+        }
+    }
 
     updated_lines
 }
 
+// テンプレートの形のままで一意に解けない場合はNone
+fn generate_problem(pattern: &Grid, template_lines: &[Vec<i32>], symmetry: Symmetry, rng: &mut impl Rng) -> Option<Grid> {
+    let mut hint_lines = add_blank_to_template(template_lines, 5, symmetry, rng);
+    let mut problem = fit_template(pattern, &hint_lines);
+    if !is_proper(&problem) {
+        return None;
+    }
+
+    // 論理で解けなくなるか解が一意でなくなったら、直前の盤面を問題とする。
+    // テンプレートで消せるヒントが残っていなければそこで終える
+    loop {
+        let next_hint_lines = add_blank_to_template(&hint_lines, 2, symmetry, rng);
+        if next_hint_lines == hint_lines {
+            break;
+        }
+        let fit_grid = fit_template(pattern, &next_hint_lines);
+
+        if !is_proper(&fit_grid) {
+            break;
+        }
+        hint_lines = next_hint_lines;
+        problem = fit_grid;
+    }
+
+    Some(problem)
+}
+
+// ヒントを消した盤面を問題として残してよいか。一意解で、目標があれば目標の難易度に収まるもの。
+// 解が複数ある盤面の評価はすべての手法を試すので遅く、先に一意性を調べる
+fn is_acceptable_after_removal(problem: &Grid, target: Option<&Target>) -> bool {
+    number_place_lib::has_unique_solution(problem)
+        && target.is_none_or(|x| x.allows(&number_place_lib::rate_problem(problem)))
+}
+
+// 軌道ごとにヒントを消してみて、消しても問題として残せるものだけ消す。
+// deadlineを過ぎたら、それまでに消した盤面を返す
+fn remove_clues(problem: &Grid, orbits: &[Vec<(usize, usize)>], target: Option<&Target>, deadline: Option<Instant>) -> Grid {
+    let mut problem = *problem;
+    for orbit in orbits {
+        if deadline.is_some_and(|x| Instant::now() >= x) {
+            break;
+        }
+        if orbit.iter().all(|(row_index, col_index)| problem.get(*row_index, *col_index).is_none()) {
+            continue;
+        }
+
+        let prev_problem = problem;
+        for (row_index, col_index) in orbit {
+            problem.set(*row_index, *col_index, None);
+        }
+
+        if !is_acceptable_after_removal(&problem, target) {
+            problem = prev_problem;
+        }
+    }
+
+    problem
+}
+
+// 冗長なヒントが残らないよう消せるだけ消す。対称性やテンプレートの制約で消せないものが残る場合や、
+// 目標がなくシングルとサブセットで解けない場合はNone
+fn minimize(problem: &Grid, template_lines: &[Vec<i32>], symmetry: Symmetry, target: Option<&Target>, rng: &mut impl Rng) -> Option<Grid> {
+    let mut orbits = removable_orbits(template_lines, symmetry);
+    orbits.shuffle(rng);
+
+    let minimized = remove_clues(problem, &orbits, target, None);
+    // 目標がなければ、通常の生成と同じくシングルとサブセットで解けるものだけを残す
+    let is_accepted = number_place_lib::is_minimal(&minimized) && (target.is_some() || is_proper(&minimized));
+    match is_accepted {
+        true => Some(minimized),
+        false => None,
+    }
+}
+
+// ヒントを消す順番を変えながら、目標の難易度に収まる一意解の問題が見つかるまで探す
+fn search_problem(pattern: &Grid, template_lines: &[Vec<i32>], symmetry: Symmetry, target: &Target, budget: &Budget, rng: &mut impl Rng) -> Option<Grid> {
+    let started_at = Instant::now();
+    let deadline = started_at + budget.time_limit;
+
+    let template_orbits = removable_orbits(template_lines, symmetry);
+
+    let mut attempt = 0;
+    while attempt < budget.max_attempts && started_at.elapsed() < budget.time_limit {
+        attempt += 1;
+
+        let mut orbits = template_orbits.clone();
+        orbits.shuffle(rng);
+
+        let problem = remove_clues(&fit_template(pattern, template_lines), &orbits, Some(target), Some(deadline));
+
+        if number_place_lib::has_unique_solution(&problem) && target.accepts(&number_place_lib::rate_problem(&problem)) {
+            return Some(problem);
+        }
+    }
+
+    None
+}
+
 fn main() {
     let cli_options = App::new("number_place_problem_generator")
         .about("Number Place Problem Generator")
@@ -90,62 +341,195 @@ fn main() {
             .required(true)
             .takes_value(true)
         )
+        .arg(Arg::with_name("template")
+            .help("Template JSON Path")
+            .long("template")
+            .short("t")
+            .takes_value(true)
+            .conflicts_with("template_dir")
+        )
+        .arg(Arg::with_name("template_dir")
+            .help("Template JSON Dir Path")
+            .long("template_dir")
+            .takes_value(true)
+        )
+        .arg(Arg::with_name("symmetry")
+            .help("Symmetry Of Clues")
+            .long("symmetry")
+            .short("s")
+            .takes_value(true)
+            .possible_values(&["none", "rot180", "rot90", "horizontal", "vertical", "diagonal", "dihedral"])
+            .default_value("none")
+        )
+        .arg(Arg::with_name("minimal")
+            .help("Remove All Redundant Clues")
+            .long("minimal")
+            .short("m")
+        )
+        .arg(Arg::with_name("seed")
+            .help("Random Seed")
+            .long("seed")
+            .takes_value(true)
+        )
+        .arg(Arg::with_name("difficulty")
+            .help("Target Difficulty")
+            .long("difficulty")
+            .short("d")
+            .takes_value(true)
+            .possible_values(&["easy", "medium", "hard", "expert", "master"])
+        )
+        .arg(Arg::with_name("min_score")
+            .help("Minimum Rating Score")
+            .long("min_score")
+            .takes_value(true)
+        )
+        .arg(Arg::with_name("max_score")
+            .help("Maximum Rating Score")
+            .long("max_score")
+            .takes_value(true)
+        )
+        .arg(Arg::with_name("max_attempts")
+            .help("Maximum Attempts Per Pattern")
+            .long("max_attempts")
+            .takes_value(true)
+            .default_value("100")
+        )
+        .arg(Arg::with_name("time_limit")
+            .help("Time Limit Per Pattern (sec)")
+            .long("time_limit")
+            .takes_value(true)
+            .default_value("60")
+        )
         .get_matches();
     
     let p_pattern_dir_path = cli_options.value_of("pattern_dir").unwrap();
-    let pattern_path_list: Vec<PathBuf> = read_dir(p_pattern_dir_path).unwrap()
-        .map(|res| res.unwrap().path())
-        .filter(|path| !path.is_dir() && path.extension().unwrap_or(OsStr::new("")) == "json")
-        .collect();
+    let pattern_path_list: Vec<PathBuf> = json_paths(p_pattern_dir_path);
 
-    let template_lines = vec![
-        vec![1,1,1,1,1,1,1,1,1],
-        vec![1,1,1,1,1,1,1,1,1],
-        vec![1,1,1,1,1,1,1,1,1],
-        vec![1,1,1,1,1,1,1,1,1],
-        vec![1,1,1,1,1,1,1,1,1],
-        vec![1,1,1,1,1,1,1,1,1],
-        vec![1,1,1,1,1,1,1,1,1],
-        vec![1,1,1,1,1,1,1,1,1],
-        vec![1,1,1,1,1,1,1,1,1],
-    ];
+    // テンプレートが複数ある場合はパターンごとに順番に使う。名前は出力ファイルに記録する
+    let template_list: Vec<(String, Vec<Vec<i32>>)> = if let Some(p_template_path) = cli_options.value_of("template") {
+        vec![(template_name(Path::new(p_template_path)), load_template(Path::new(p_template_path)))]
+    } else if let Some(p_template_dir_path) = cli_options.value_of("template_dir") {
+        json_paths(p_template_dir_path).iter()
+            .map(|path| (template_name(path), load_template(path)))
+            .collect()
+    } else {
+        vec![("default".to_string(), default_template())]
+    };
+    if template_list.is_empty() {
+        panic!("no template found");
+    }
 
     let p_output_dir_path = cli_options.value_of("output_dir").unwrap();
     let output_dir_path = Path::new(p_output_dir_path);
 
-    for pattern_path in pattern_path_list {
-        let pattern_file = File::open(&pattern_path).unwrap();
+    let target = Target {
+        difficulty: cli_options.value_of("difficulty").map(|x| x.parse().unwrap()),
+        min_score: cli_options.value_of("min_score").map(|x| x.parse().unwrap()),
+        max_score: cli_options.value_of("max_score").map(|x| x.parse().unwrap()),
+    };
+    if let (Some(min_score), Some(max_score)) = (target.min_score, target.max_score) {
+        if min_score > max_score {
+            panic!("min_score {} is greater than max_score {}", min_score, max_score);
+        }
+    }
+    // シードが指定されなければ生成して出力ファイルに記録し、後から同じ問題を再生成できるようにする
+    let seed: u64 = match cli_options.value_of("seed") {
+        Some(p_seed) => p_seed.parse().unwrap(),
+        None => rand::thread_rng().gen(),
+    };
+
+    let symmetry: Symmetry = cli_options.value_of("symmetry").unwrap().parse().unwrap();
+    let is_minimal_required = cli_options.is_present("minimal");
+    let is_target_specified = target.difficulty.is_some() || target.min_score.is_some() || target.max_score.is_some();
+    let budget = Budget {
+        max_attempts: cli_options.value_of("max_attempts").unwrap().parse().unwrap(),
+        time_limit: Duration::from_secs(cli_options.value_of("time_limit").unwrap().parse().unwrap()),
+    };
+
+    for (pattern_index, pattern_path) in pattern_path_list.iter().enumerate() {
+        let pattern_file = File::open(pattern_path).unwrap();
         let pattern_reader = BufReader::new(pattern_file);
-        let pattern_lines: Vec<Vec<i32>> = serde_json::from_reader(pattern_reader).unwrap();
+        let pattern_file: PatternFile = serde_json::from_reader(pattern_reader).unwrap();
+        let pattern = Grid::from_lines(pattern_file.lines()).unwrap();
         let pattern_file_name = pattern_path.file_name().unwrap().to_str().unwrap();
 
-        let mut hint_lines: Vec<Vec<i32>> = template_lines.clone();
-
-        let mut number_of_blanks = 5;
-        hint_lines = add_blank_to_template(&hint_lines, 5);
-
-        let mut is_solved = true;
-        let mut prev_fit_lines: Vec<Vec<i32>> = Vec::new();
-        while is_solved {
-            number_of_blanks = number_of_blanks + 2;
-            hint_lines = add_blank_to_template(&hint_lines, 2);
-        
-            let fit_lines = fit_template(&pattern_lines, &hint_lines);
-        
-            is_solved = is_solvable(&fit_lines);
-            prev_fit_lines = fit_lines.clone();
-        }
+        let (template_name, template_lines) = &template_list[pattern_index % template_list.len()];
+        // パターンごとに乱数を分け、他のパターンの結果に影響されないようにする。
+        // 出力にはこのシードを記録するので、パターン1つだけを同じシードとテンプレートで生成し直せる
+        let pattern_seed = seed.wrapping_add(pattern_index as u64);
+        let mut rng = StdRng::seed_from_u64(pattern_seed);
 
-        let json = serde_json::to_string(&prev_fit_lines).unwrap()
-            .replace("[[", "[\n    [")
-            .replace("],", "],\n    ")
-            .replace("]]", "]\n]");
+        let problem = if is_target_specified {
+            match search_problem(&pattern, template_lines, symmetry, &target, &budget, &mut rng) {
+                Some(problem) => problem,
+                None => {
+                    eprintln!("{}: no problem found in the target difficulty", pattern_file_name);
+                    continue;
+                }
+            }
+        } else {
+            match generate_problem(&pattern, template_lines, symmetry, &mut rng) {
+                Some(problem) => problem,
+                None => {
+                    eprintln!("{}: no unique problem fits the template", pattern_file_name);
+                    continue;
+                }
+            }
+        };
+        let problem = if is_minimal_required {
+            let minimize_target = match is_target_specified {
+                true => Some(&target),
+                false => None,
+            };
+            match minimize(&problem, template_lines, symmetry, minimize_target, &mut rng) {
+                Some(problem) if !is_target_specified || target.accepts(&number_place_lib::rate_problem(&problem)) => problem,
+                _ => {
+                    eprintln!("{}: no minimal problem found", pattern_file_name);
+                    continue;
+                }
+            }
+        } else {
+            problem
+        };
+        let number_of_blanks = problem.count_empty();
+        let is_unique = number_place_lib::has_unique_solution(&problem);
+
+        let rating = number_place_lib::rate_problem(&problem);
+        let problem_json = serde_json::to_string(&problem.to_lines()).unwrap()
+            .replace("[[", "[\n        [")
+            .replace("],", "],\n        ")
+            .replace("]]", "]\n    ]");
+        let rating_json = serde_json::to_string(&RatingInfo::from(&rating)).unwrap();
+        let template_json = serde_json::to_string(template_name).unwrap();
+        let json = format!("{{\n    \"problem\": {},\n    \"rating\": {},\n    \"seed\": {},\n    \"template\": {}\n}}", problem_json, rating_json, pattern_seed, template_json);
 
         let output_file_path = output_dir_path.join(pattern_file_name);
         let mut f = BufWriter::new(File::create(output_file_path).unwrap());
         f.write_all(json.as_bytes()).unwrap();
         f.flush().unwrap();
 
-        println!("{}_{}", number_of_blanks, pattern_file_name);
+        println!("{}_{} {} {}", number_of_blanks, pattern_file_name, match is_unique {
+            true => "unique",
+            false => "not unique",
+        }, rating);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ほとんどのマスが必ず残すヒントのテンプレートでも、消せるヒントが無くなった時点で止まる
+    #[test]
+    fn generate_problem_stops_when_no_clue_is_removable() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let pattern = number_place_lib::generate_solution(&mut rng);
+        let mut template_lines = vec![vec![TEMPLATE_FIXED; 9]; 9];
+        template_lines[0][0] = TEMPLATE_REMOVABLE;
+        template_lines[4][4] = TEMPLATE_REMOVABLE;
+
+        let problem = generate_problem(&pattern, &template_lines, Symmetry::None, &mut rng).unwrap();
+        assert_eq!(problem.count_empty(), 2);
+        assert!(problem.get(0, 0).is_none() && problem.get(4, 4).is_none());
     }
 }
@@ -3,7 +3,10 @@ extern crate number_place_lib;
 use crate::components::number_selector::{NumberSelector};
 use crate::components::board::{Board};
 
+use number_place_lib::{Digit, Grid};
+
 use anyhow::Result;
+use serde::Deserialize;
 use yew::{format::{Json, Nothing}, prelude::*};
 use yew::services::fetch::{FetchService, FetchTask, Request, Response};
 use yew::services::console::ConsoleService;
@@ -24,42 +27,51 @@ pub struct NumberCell {
 }
 
 struct Numbers {
-    entries: Vec<Vec<i32>>,
-    hints: Vec<Vec<i32>>,
+    entries: Grid,
+    hints: Grid,
 }
 
 impl Numbers {
     fn number_cells(&self) -> Vec<NumberCell> {
         let mut cells: Vec<NumberCell> = Vec::new();
-        for (row_index, row) in self.entries.iter().enumerate() {
-            for (col_index, col) in row.iter().enumerate() {
-                cells.push(NumberCell{
-                    num_text: match col {
-                        0 => String::from(""),
-                        _ => format!("{}", col),
-                    },
-                    is_hint: self.hints[row_index][col_index] != 0,
-                    row: row_index,
-                    col: col_index,
-                });
-            }
+        for (row_index, col_index, value) in self.entries.cells() {
+            cells.push(NumberCell{
+                num_text: match value {
+                    None => String::from(""),
+                    Some(digit) => format!("{}", digit),
+                },
+                is_hint: self.hints.get(row_index, col_index).is_some(),
+                row: row_index,
+                col: col_index,
+            });
         }
     
         cells
     }
 
-    fn remaining_cell_count(&self, entries: Vec<Vec<i32>>) -> usize {
-        entries.iter()
-            .fold(0, |sum, row| {
-                let zero_count: usize = row.iter()
-                    .filter(|&col| *col == 0)
-                    .count();
-                sum + zero_count
-            })
+    fn remaining_cell_count(&self, entries: &Grid) -> usize {
+        entries.count_empty()
     }
 
     fn are_entries_solved(&self) -> bool {
-        number_place_lib::check_solved(&self.entries)
+        self.entries.is_solved()
+    }
+}
+
+// 問題ファイルは数字の配列のみの形式と評価などを含む形式のどちらも読める
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ProblemFile {
+    Lines(Vec<Vec<i32>>),
+    WithMetadata { problem: Vec<Vec<i32>> },
+}
+
+impl ProblemFile {
+    fn lines(&self) -> &[Vec<i32>] {
+        match self {
+            ProblemFile::Lines(lines) => lines,
+            ProblemFile::WithMetadata { problem } => problem,
+        }
     }
 }
 
@@ -76,7 +88,7 @@ pub enum Msg {
     CellClick(NumberCell),
     NumberClick(i32),
     ResetClick(),
-    NewGameResponse(Result<Vec<Vec<i32>>, anyhow::Error>),
+    NewGameResponse(Result<ProblemFile, anyhow::Error>),
 }
 
 impl Game {
@@ -139,20 +151,9 @@ impl Component for Game {
     type Properties = Props;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        let hints = vec![
-            vec![0,0,0,0,0,0,0,0,0],
-            vec![0,0,0,0,0,0,0,0,0],
-            vec![0,0,0,0,0,0,0,0,0],
-            vec![0,0,0,0,0,0,0,0,0],
-            vec![0,0,0,0,0,0,0,0,0],
-            vec![0,0,0,0,0,0,0,0,0],
-            vec![0,0,0,0,0,0,0,0,0],
-            vec![0,0,0,0,0,0,0,0,0],
-            vec![0,0,0,0,0,0,0,0,0],
-        ];
         let numbers = Numbers{
-            hints: hints.clone(),
-            entries: hints.clone(),
+            hints: Grid::empty(),
+            entries: Grid::empty(),
         };
 
         link.send_message(Msg::ResetClick());
@@ -183,7 +184,7 @@ impl Component for Game {
             Msg::NumberClick(num) =>  {
                 //ConsoleService::info(format!("Number Click: {}", &num).as_str());
 
-                self.numbers.entries[self.target_cell.row][self.target_cell.col] = num;
+                self.numbers.entries.set(self.target_cell.row, self.target_cell.col, Digit::from_i32(num));
             },
             Msg::ResetClick() => {
                 let url_path = document().location().unwrap().pathname().unwrap().replace("/index.html", "");
@@ -192,7 +193,7 @@ impl Component for Game {
                     .body(Nothing)
                     .expect("Could not build request.");
                 let callback = self.link
-                    .callback(|response: Response<Json<Result<Vec<Vec<i32>>, anyhow::Error>>>| {
+                    .callback(|response: Response<Json<Result<ProblemFile, anyhow::Error>>>| {
                         let Json(data) = response.into_body();
                         Msg::NewGameResponse(data)
                     });
@@ -202,10 +203,10 @@ impl Component for Game {
             },
             Msg::NewGameResponse(res) => {
                 //ConsoleService::info(format!("NewGameResponse").as_str());
-                match res {
+                match res.and_then(|file| Ok(Grid::from_lines(file.lines())?)) {
                     Ok(hints) => {
-                        self.numbers.hints = hints.clone();
-                        self.numbers.entries = hints.clone();
+                        self.numbers.hints = hints;
+                        self.numbers.entries = hints;
                     }
                     Err(error) => {
                         ConsoleService::info(format!("New game error: {}", error).as_str());
@@ -232,8 +233,8 @@ impl Component for Game {
             .callback(|num: i32| Msg::NumberClick(num));
         let transform = format!("translate({}, {})", self.props.x, self.props.y);
 
-        let hint_zero_count = self.numbers.remaining_cell_count(self.numbers.hints.clone());
-        let entry_zero_count = self.numbers.remaining_cell_count(self.numbers.entries.clone());
+        let hint_zero_count = self.numbers.remaining_cell_count(&self.numbers.hints);
+        let entry_zero_count = self.numbers.remaining_cell_count(&self.numbers.entries);
 
         let solved_opacity = if self.numbers.are_entries_solved() {
             "1.0"
@@ -20,7 +20,7 @@ pub enum Msg {
 impl NumberSelector {
     fn view_number_selector(&self, index: i32) -> Html {
         let point = index * 40 - 40;
-        let num = index.clone() as i32;
+        let num = index;
         let fill = if self.target_number == index {
             "#ccf"
         } else {